@@ -0,0 +1,52 @@
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+
+/// The posts of one year on the archive page, by month.
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchiveYear<T> {
+    pub year: i32,
+    pub count: usize,
+    pub months: Vec<ArchiveMonth<T>>,
+}
+
+/// The posts of one month on the archive page.
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchiveMonth<T> {
+    /// the full name of the month, e.g. "March"
+    pub month: String,
+    pub count: usize,
+    pub posts: Vec<T>,
+}
+
+/// Groups posts (already sorted by date) by year and then month, keeping their order.
+pub fn group_by_month<T>(posts: Vec<(DateTime<Utc>, T)>) -> Vec<ArchiveYear<T>> {
+    let mut years: Vec<ArchiveYear<T>> = Vec::new();
+    let mut last_month: Option<(i32, u32)> = None;
+
+    for (date, post) in posts {
+        if years.last().map(|y| y.year) != Some(date.year()) {
+            years.push(ArchiveYear {
+                year: date.year(),
+                count: 0,
+                months: Vec::new(),
+            });
+        }
+        let year = years.last_mut().unwrap();
+
+        if last_month != Some((date.year(), date.month())) {
+            year.months.push(ArchiveMonth {
+                month: date.format("%B").to_string(),
+                count: 0,
+                posts: Vec::new(),
+            });
+            last_month = Some((date.year(), date.month()));
+        }
+        let month = year.months.last_mut().unwrap();
+
+        month.posts.push(post);
+        month.count += 1;
+        year.count += 1;
+    }
+
+    years
+}
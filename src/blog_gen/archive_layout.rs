@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+
+/// How the rendered posts are organized into date based subfolders of the rendered
+/// outputs dir.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ArchiveLayout {
+    /// `2024/post.html`
+    Year,
+
+    /// `2024/03/post.html`
+    #[value(name = "year/month")]
+    YearMonth,
+
+    /// `2024/03/15/post.html`
+    #[value(name = "year/month/day")]
+    YearMonthDay,
+}
+
+impl ArchiveLayout {
+    /// The dir (with a trailing `/`) for a post dated `date`, relative to the rendered
+    /// outputs dir.
+    pub fn dir(&self, date: &DateTime<Utc>) -> String {
+        match self {
+            ArchiveLayout::Year => date.format("%Y/").to_string(),
+            ArchiveLayout::YearMonth => date.format("%Y/%m/").to_string(),
+            ArchiveLayout::YearMonthDay => date.format("%Y/%m/%d/").to_string(),
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use image::ImageFormat;
+use scraper::{Html, Selector};
+
+use super::blog_generator::BlogGeneratorError;
+use super::file_mode::set_file_mode;
+use super::html_rewrite::{element_attrs, opening_tag, rewrite_opening_tags, set_attr};
+
+/// Returns true if an image `src` refers to a file relative to the markdown sources,
+/// rather than a remote url, a data uri or an absolute path on the site.
+pub fn is_local_asset(src: &str) -> bool {
+    !(src.is_empty()
+        || src.contains("://")
+        || src.starts_with("//")
+        || src.starts_with('/')
+        || src.starts_with("data:")
+        || src.starts_with('#'))
+}
+
+/// Returns the `src` of every local image referenced in the rendered html.
+pub fn local_image_sources(html: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("img").unwrap();
+
+    fragment
+        .select(&selector)
+        .filter_map(|img| img.value().attr("src"))
+        .map(strip_query_and_fragment)
+        .filter(|src| is_local_asset(src))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns the local `src` (and `poster`) of every `<video>` and `<audio>` in the
+/// rendered html.
+pub fn local_media_sources(html: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("video, audio").unwrap();
+
+    fragment
+        .select(&selector)
+        .flat_map(|media| [media.value().attr("src"), media.value().attr("poster")])
+        .flatten()
+        .map(strip_query_and_fragment)
+        .filter(|src| is_local_asset(src))
+        .map(str::to_string)
+        .collect()
+}
+
+fn strip_query_and_fragment(src: &str) -> &str {
+    src.split(['?', '#']).next().unwrap_or(src)
+}
+
+/// Copies the asset at `src` (relative to `source_dir`) to the same relative location
+/// under `output_dir`, creating any intermediate directories. Paths that would escape
+/// the source or output directory (via `..`) are rejected.
+pub fn copy_asset(
+    source_dir: &str,
+    output_dir: &str,
+    src: &str,
+) -> Result<PathBuf, BlogGeneratorError> {
+    let relative = Path::new(src);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(BlogGeneratorError::AssetError(
+            src.to_string(),
+            "asset paths must stay within the markdown sources dir".to_string(),
+        ));
+    }
+
+    let from = Path::new(source_dir).join(relative);
+    let to = Path::new(output_dir).join(relative);
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| BlogGeneratorError::AssetError(src.to_string(), e.to_string()))?;
+    }
+    fs::copy(&from, &to)
+        .map_err(|e| BlogGeneratorError::AssetError(src.to_string(), e.to_string()))?;
+
+    Ok(to)
+}
+
+/// Writes a WebP version of the image at `path` alongside it (`a.png.webp`, so that
+/// `a.png` and `a.jpg` don't share one), returning the path of the new file. Images that are already WebP are skipped (returning `Ok(None)`).
+pub fn convert_to_webp(path: &Path) -> Result<Option<PathBuf>, String> {
+    if ImageFormat::from_path(path).ok() == Some(ImageFormat::WebP) {
+        return Ok(None);
+    }
+
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let mut webp_path = path.as_os_str().to_owned();
+    webp_path.push(".webp");
+    let webp_path = PathBuf::from(webp_path);
+
+    // the WebP encoder only accepts 8-bit images
+    let img = if img.color().has_alpha() {
+        image::DynamicImage::ImageRgba8(img.to_rgba8())
+    } else {
+        image::DynamicImage::ImageRgb8(img.to_rgb8())
+    };
+    img.save_with_format(&webp_path, ImageFormat::WebP)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(webp_path))
+}
+
+/// The mime type of a favicon, or `None` if it isn't in a supported format.
+pub fn favicon_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "ico" => Some("image/x-icon"),
+        "png" => Some("image/png"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// Returns the `src` of the WebP version of an image (see `convert_to_webp`).
+pub fn webp_src(src: &str) -> String {
+    format!("{}.webp", src)
+}
+
+/// Wraps each `<img>` for which `has_webp` returns true in a `<picture>`, offering the
+/// WebP version as a source with the original image as the fallback.
+pub fn rewrite_images_as_pictures<F>(html: &str, has_webp: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    rewrite_opening_tags(html, "img", |tag, img| {
+        let src = strip_query_and_fragment(img.attr("src")?);
+        if !has_webp(src) {
+            return None;
+        }
+
+        Some(format!(
+            "<picture><source srcset=\"{}\" type=\"image/webp\">{}</picture>",
+            html_escape::encode_double_quoted_attribute(&webp_src(src)),
+            tag
+        ))
+    })
+}
+
+/// Recursively copies the contents of `from` into `to`, returning the number of files copied.
+/// The copies are given the `file_mode` permissions, when there are any.
+pub fn copy_dir_recursive(
+    from: &Path,
+    to: &Path,
+    file_mode: Option<u32>,
+) -> std::io::Result<usize> {
+    fs::create_dir_all(to)?;
+
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir_recursive(&entry.path(), &target, file_mode)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+            if let Some(file_mode) = file_mode {
+                set_file_mode(&target, file_mode)?;
+            }
+            copied += 1;
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Prefixes the local image sources (including the WebP `<source>`s of a `<picture>`) with
+/// `prefix`, for posts that are written to a subfolder of the dir the images were copied to.
+pub fn prefix_local_image_sources(html: &str, prefix: &str) -> String {
+    let html = rewrite_opening_tags(html, "img", |_, img| {
+        let mut attrs = element_attrs(img);
+        set_attr(&mut attrs, "src", &prefixed(img.attr("src")?, prefix)?);
+        Some(opening_tag("img", &attrs))
+    });
+    rewrite_opening_tags(&html, "source", |_, source| {
+        let mut attrs = element_attrs(source);
+        set_attr(
+            &mut attrs,
+            "srcset",
+            &prefixed(source.attr("srcset")?, prefix)?,
+        );
+        Some(opening_tag("source", &attrs))
+    })
+}
+
+/// Prefixes the local sources (and posters) of the `<video>`s and `<audio>`s with `prefix`,
+/// like `prefix_local_image_sources`.
+pub fn prefix_local_media_sources(html: &str, prefix: &str) -> String {
+    ["video", "audio"]
+        .iter()
+        .fold(html.to_string(), |html, tag| {
+            rewrite_opening_tags(&html, tag, |_, media| {
+                let mut attrs = element_attrs(media);
+                for name in ["src", "poster"] {
+                    if let Some(value) = media.attr(name).and_then(|value| prefixed(value, prefix))
+                    {
+                        set_attr(&mut attrs, name, &value);
+                    }
+                }
+                Some(opening_tag(tag, &attrs))
+            })
+        })
+}
+
+fn prefixed(value: &str, prefix: &str) -> Option<String> {
+    if is_local_asset(value) {
+        Some(format!("{}{}", prefix, value))
+    } else {
+        None
+    }
+}
+
+/// Replaces the `src` of each local image (relative to `source_dir`) no bigger than
+/// `max_bytes` with a `data:` uri holding the image itself, so that small images like icons
+/// don't need files of their own. Larger images, and those that can't be read or aren't in
+/// a known image format, are left referring to their files.
+pub fn inline_small_images(html: &str, source_dir: &str, max_bytes: u64) -> String {
+    rewrite_opening_tags(html, "img", |_, img| {
+        let src = strip_query_and_fragment(img.attr("src")?);
+        if !is_local_asset(src) {
+            return None;
+        }
+        let path = Path::new(source_dir).join(src);
+        if fs::metadata(&path).ok()?.len() > max_bytes {
+            return None;
+        }
+        let mime = image_mime_type(&path)?;
+        let bytes = fs::read(&path).ok()?;
+
+        let mut attrs = element_attrs(img);
+        set_attr(
+            &mut attrs,
+            "src",
+            &format!("data:{};base64,{}", mime, base64_encode(&bytes)),
+        );
+        Some(opening_tag("img", &attrs))
+    })
+}
+
+/// The mime type of an image, by its extension.
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    if extension == "svg" {
+        return Some("image/svg+xml");
+    }
+    let format = ImageFormat::from_extension(extension)?;
+    format.reading_enabled().then(|| format.to_mime_type())
+}
+
+/// Encodes bytes in (padded) base64, as used by `data:` uris.
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webp_versions_keep_the_original_extension() {
+        let dir = std::env::temp_dir().join(format!("md-blog-gen-webp-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let png = dir.join("a.png");
+        image::RgbImage::new(2, 2).save(&png).unwrap();
+
+        let webp = convert_to_webp(&png).unwrap().unwrap();
+        assert_eq!(webp, dir.join("a.png.webp"));
+        assert!(webp.is_file());
+        assert_eq!(webp_src("img/a.png"), "img/a.png.webp");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pictures_offer_the_webp_version() {
+        let html =
+            rewrite_images_as_pictures("<img src=\"a.png\" alt=\"a\">", |src| src == "a.png");
+        assert!(html.starts_with("<picture><source srcset=\"a.png.webp\" type=\"image/webp\">"));
+    }
+
+    #[test]
+    fn only_local_sources_are_prefixed() {
+        let html = prefix_local_image_sources(
+            "<img src=\"a.png\"><img src=\"https://example.com/b.png\">",
+            "../",
+        );
+        assert!(html.contains("src=\"../a.png\""));
+        assert!(html.contains("src=\"https://example.com/b.png\""));
+
+        let html = prefix_local_media_sources("<video src=\"v.mp4\" poster=\"p.png\">", "../");
+        assert!(html.contains("src=\"../v.mp4\"") && html.contains("poster=\"../p.png\""));
+    }
+}
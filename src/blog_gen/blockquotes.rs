@@ -0,0 +1,147 @@
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+/// The start of the line attributing a blockquote to someone.
+const ATTRIBUTION_PREFIX: &str = "— ";
+
+/// Pulls the attribution out of each blockquote whose last line starts with an em dash
+/// (`— Name`, whether on a line of its own or in a paragraph of its own) into a
+/// `<footer><cite>Name</cite></footer>` at the end of the blockquote. Blockquotes without
+/// one are left as they are.
+///
+/// The blockquotes ending in a paragraph are found with scraper, and only the markup of
+/// that paragraph is changed.
+pub fn add_blockquote_citations(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("blockquote").unwrap();
+    let spans = blockquote_spans(html);
+
+    // the blockquotes come in the same (document) order as their opening tags; the end of
+    // each is changed, from the end of the html back so the earlier spans stay put
+    let mut citations: Vec<(usize, usize, String)> = fragment
+        .select(&selector)
+        .zip(spans)
+        .filter(|(blockquote, _)| ends_with_paragraph(*blockquote))
+        .filter_map(|(_, (start, end))| {
+            let (from, cited) = cite(&html[start..end])?;
+            Some((start + from, end, cited))
+        })
+        .collect();
+    citations.sort_by_key(|(from, _, _)| std::cmp::Reverse(*from));
+
+    let mut cited = html.to_string();
+    for (from, end, citation) in citations {
+        cited.replace_range(from..end, &citation);
+    }
+
+    cited
+}
+
+/// The spans of the markup of each blockquote in the html, in the order they open.
+fn blockquote_spans(html: &str) -> Vec<(usize, usize)> {
+    let tag_re = Regex::new(r"(?i)<(/?)blockquote\b[^>]*>").unwrap();
+
+    let mut spans = Vec::new();
+    let mut open = Vec::new();
+    for tag in tag_re.captures_iter(html) {
+        let whole = tag.get(0).unwrap();
+        if tag[1].is_empty() {
+            open.push(spans.len());
+            spans.push((whole.start(), html.len()));
+        } else if let Some(i) = open.pop() {
+            spans[i].1 = whole.end();
+        }
+    }
+
+    spans
+}
+
+/// Whether the last thing in the blockquote (besides whitespace) is a paragraph.
+fn ends_with_paragraph(blockquote: ElementRef) -> bool {
+    blockquote
+        .children()
+        .filter(|child| {
+            child
+                .value()
+                .as_text()
+                .is_none_or(|text| !text.trim().is_empty())
+        })
+        .last()
+        .and_then(ElementRef::wrap)
+        .is_some_and(|last| last.value().name() == "p")
+}
+
+/// The end of the blockquote with its last paragraph's attribution turned into a citation,
+/// along with where in the blockquote it starts, or `None` when that paragraph has no
+/// attribution.
+fn cite(blockquote: &str) -> Option<(usize, String)> {
+    const QUOTE_END: &str = "</p>\n</blockquote>";
+
+    let end = blockquote.strip_suffix(QUOTE_END)?.len();
+    // the paragraph ending the blockquote, which its attribution would end
+    let start = blockquote[..end].rfind("<p>")? + "<p>".len();
+    let paragraph = &blockquote[start..end];
+    let (quote, attribution) = match paragraph.rsplit_once('\n') {
+        Some((quote, attribution)) => (Some(quote), attribution),
+        None => (None, paragraph),
+    };
+    let name = attribution
+        .strip_prefix(ATTRIBUTION_PREFIX)
+        .map(str::trim)
+        .filter(|name| has_text(name))?;
+
+    let citation = format!("<footer><cite>{}</cite></footer>\n</blockquote>", name);
+    match quote {
+        // the rest of the paragraph is still part of the quote
+        Some(quote) => Some((start, format!("{}</p>\n{}", quote, citation))),
+        None => Some((start - "<p>".len(), citation)),
+    }
+}
+
+/// Whether the html has any text in it, rather than just markup.
+fn has_text(html: &str) -> bool {
+    Html::parse_fragment(html)
+        .root_element()
+        .text()
+        .any(|text| !text.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributions_on_their_own_line_become_citations() {
+        let html = "<blockquote>\n<p>Be excellent.\n— Bill</p>\n</blockquote>\n";
+        assert_eq!(
+            add_blockquote_citations(html),
+            "<blockquote>\n<p>Be excellent.</p>\n<footer><cite>Bill</cite></footer>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn attributions_in_a_paragraph_of_their_own_become_citations() {
+        let html = "<blockquote>\n<p>Be excellent.</p>\n<p>— <em>Bill</em></p>\n</blockquote>\n";
+        assert_eq!(
+            add_blockquote_citations(html),
+            "<blockquote>\n<p>Be excellent.</p>\n<footer><cite><em>Bill</em></cite></footer>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn blockquotes_without_an_attribution_are_left_unchanged() {
+        let html = "<p>— not a quote</p>\n<blockquote>\n<ul>\n<li>a</li>\n</ul>\n</blockquote>\n\
+                    <blockquote>\n<p>Just a quote.</p>\n</blockquote>\n";
+        assert_eq!(add_blockquote_citations(html), html);
+    }
+
+    #[test]
+    fn nested_blockquotes_are_cited_separately() {
+        let html = "<blockquote>\n<blockquote>\n<p>inner\n— A</p>\n</blockquote>\n<p>outer\n— B</p>\n</blockquote>\n";
+        assert_eq!(
+            add_blockquote_citations(html),
+            "<blockquote>\n<blockquote>\n<p>inner</p>\n<footer><cite>A</cite></footer>\n</blockquote>\n\
+             <p>outer</p>\n<footer><cite>B</cite></footer>\n</blockquote>\n"
+        );
+    }
+}
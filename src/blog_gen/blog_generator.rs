@@ -1,16 +1,98 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::{self, File};
-use std::io::prelude::*;
-use std::path::PathBuf;
+use std::io::{prelude::*, IsTerminal};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
+use ab_glyph::FontVec;
 use chrono::{DateTime, Utc};
 use glob::glob;
-use pulldown_cmark::{html, Options, Parser};
+use image::DynamicImage;
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, warn};
+use pulldown_cmark::{html, Event, HeadingLevel, Options, Parser, Tag};
 use scraper::{Html, Selector};
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use tera::Tera;
 use thiserror::Error;
 
-use super::html_template::{get_html_template, get_index_page_template};
+use super::archive::group_by_month;
+use super::archive_layout::ArchiveLayout;
+use super::assets::{
+    convert_to_webp, copy_asset, copy_dir_recursive, favicon_type, inline_small_images,
+    local_image_sources, local_media_sources, prefix_local_image_sources,
+    prefix_local_media_sources, rewrite_images_as_pictures,
+};
+use super::blockquotes::add_blockquote_citations;
+use super::blogroll::Blogroll;
+use super::build_lock::{BuildLock, BUILD_LOCK_FILE};
+use super::build_summary::BuildSummary;
+use super::bundle::split_bundle;
+use super::checks::{
+    check_heading_hierarchy, find_extension_constructs, find_images_missing_alt, HeadingProblem,
+};
+use super::code_copy::add_copy_buttons;
+use super::css_imports::resolve_css_imports;
+use super::excerpt::{
+    first_paragraph, is_truncated, markdown_above_marker, strip_excerpt_marker, truncate_title,
+    without_title_heading,
+};
+use super::feed::{
+    atom_feed, json_feed, rss_feed, sort_entries, FeedChannel, FeedContent, FeedEntry, FeedFormat,
+};
+use super::figures::number_figures;
+use super::file_mode::set_file_mode;
+use super::front_matter::{
+    parse_front_matter_with, split_comment_metadata, split_front_matter, FrontMatter,
+};
+use super::git_dates::git_dates;
+use super::heading_numbers::number_headings;
+use super::html_template::{
+    get_archive_page_template, get_html_template, get_index_page_template, get_print_css,
+};
+use super::json_output::{is_reserved_file_name, JsonPost, JsonPostSummary};
+use super::links::{absolute_links, open_external_links_in_new_tab, rebase_links, relative_links};
+use super::listing::{PostList, PostListing};
+use super::manifest::{Manifest, ManifestEntry};
+use super::markdown_flavor::MarkdownFlavor;
+use super::og_image::{load_font, og_image};
+use super::output_format::{format_output, normalize_line_endings, LineEnding};
+use super::partials::expand_includes;
+use super::pdf::html_to_pdf;
+use super::post_order::PostOrder;
+use super::precompress::{brotli_compress, gzip_compress, Precompression};
+use super::problems::{line_of, ProblemReport};
+use super::reading::{word_count, DEFAULT_LONG_READ_THRESHOLD};
+use super::recent::{recent_posts_html, RecentPost};
+use super::redirects::{redirect_page, redirects_file, site_path};
+use super::render_cache::{CacheKey, RenderCache, RenderedMarkdown};
+use super::sanitize::sanitize_html;
+use super::shortcodes::{expand_shortcodes, is_shortcode_html, video_html};
+use super::since::Since;
+use super::single_page::{prefix_ids, single_page_html, SinglePagePost};
+use super::site::{Site, SiteTemplates};
+use super::slug::{slugify, transliterated_slugify};
+use super::source_encoding::{read_source, SourceEncoding};
+use super::tables::add_alignment_classes;
+use super::tags::tag_cloud;
+use super::template_rules::{rule_template_name, template_for, TemplateRule};
+use super::theme::Theme;
+use super::toc::add_table_of_contents;
+
+/// The default limit on the size of a markdown source file, 10MB, which is far more
+/// than any post should need.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// The most times a failed write of an output file is retried.
+pub const MAX_WRITE_RETRIES: u32 = 10;
+
+/// The longest wait before retrying a failed write.
+const MAX_WRITE_BACKOFF: Duration = Duration::from_secs(5);
 
 #[derive(Error, Debug)]
 pub enum BlogGeneratorError {
@@ -20,6 +102,9 @@ pub enum BlogGeneratorError {
     #[error("the css source file ({0}) could not be read: {1}")]
     CSSSourceError(String, String),
 
+    #[error("the css source file ({0}) has an @import cycle: {1}")]
+    CSSImportCycle(String, String),
+
     #[error("the path to markdown sources dir ({0}) is invalid: {1}")]
     InvalidMarkDownPath(String, String),
 
@@ -32,6 +117,9 @@ pub enum BlogGeneratorError {
     #[error("the path ({0}) to rendered output directory is invalid: {1}")]
     InvalidRenderedOutputPath(String, String),
 
+    #[error("an error occurred while copying the asset {0}: {1}")]
+    AssetError(String, String),
+
     #[error("an error occurred while attempting to write output file {0}: {1}")]
     FileWriteError(String, String),
 
@@ -40,27 +128,223 @@ pub enum BlogGeneratorError {
 
     #[error("an error occurred while attempting to use a ({0}) template: {1}")]
     TemplateUseError(String, String),
+
+    #[error("the title ({0}) is used by more than one markdown source file: {1}")]
+    DuplicateTitle(String, String),
+
+    #[error("the output file ({0}) would be written by more than one markdown source file: {1}")]
+    OutputPathCollision(String, String),
+
+    #[error("the markdown source file {0} has an image without alt text: {1}")]
+    MissingAltText(String, String),
+
+    #[error("the markdown source file {0} has a heading out of place: {1}")]
+    HeadingHierarchy(String, String),
+
+    #[error("the theme directory ({0}) is invalid: {1}")]
+    InvalidTheme(String, String),
+
+    #[error("the blogroll ({0}) is invalid: {1}")]
+    InvalidBlogroll(String, String),
+
+    #[error("the markdown source file {0} is empty")]
+    EmptyMarkDownFile(String),
+
+    #[error("the front matter of {0} is invalid: {1}")]
+    FrontMatterError(String, String),
+
+    #[error("the alias ({0}) of a post is already taken by {1}")]
+    AliasCollision(String, String),
+
+    #[error("the alias ({0}) of {1} isn't a path within the site")]
+    InvalidAlias(String, String),
+
+    #[error("the markdown source file {0} has an include that can't be resolved: {1}")]
+    IncludeError(String, String),
+
+    #[error("the markdown source files {0} and {1} would both be published at {2}")]
+    PermalinkConflict(String, String, String),
+
+    #[error("the build lock ({0}) can't be taken: {1}")]
+    BuildLocked(String, String),
+
+    #[error("the font for the Open Graph images can't be loaded: {0}")]
+    OgFontError(String),
 }
 
 #[derive(Clone, Debug, Default)]
 struct MarkDownFile {
     file_name: PathBuf,
     file_path_buf: PathBuf,
+    /// the subfolder of the rendered outputs dir the post goes in, with a trailing `/`
+    output_dir: String,
     created_time: DateTime<Utc>,
+    modified_time: DateTime<Utc>,
     title_from_md: Option<String>,
+    md_content: String,
+    /// the line of the source file that `md_content` starts on, after any front matter
+    first_body_line: usize,
+    front_matter: FrontMatter,
+}
+
+impl MarkDownFile {
+    /// the path of the rendered post, relative to the rendered outputs dir
+    fn output_path(&self) -> String {
+        format!(
+            "{}{}",
+            self.output_dir,
+            self.file_name.to_str().unwrap().replace(".md", ".html")
+        )
+    }
+
+    /// how many folders deep the rendered post is within the rendered outputs dir
+    fn output_depth(&self) -> usize {
+        self.output_dir.matches('/').count()
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize)]
 struct Page {
     title: String,
     url: String,
+    excerpt: String,
+    /// whether the post goes on past its excerpt
+    truncated: bool,
+    read_more_url: String,
+    /// whether the post has more words than the long read threshold
+    long_read: bool,
+    /// the title as shown in the index, when it's too long to show in full
+    short_title: Option<String>,
+    date: String,
+    #[serde(skip)]
+    path: String,
+    /// the post's rendered html, for the feeds
+    #[serde(skip)]
+    body_html: String,
+    #[serde(skip)]
+    published: DateTime<Utc>,
+    #[serde(skip)]
+    updated: DateTime<Utc>,
+    /// whether the post is a draft (written when drafts are asked for)
+    #[serde(skip)]
+    draft: bool,
 }
 
+/// A link to the index of the posts in one language, for switching between them.
+#[derive(Clone, Debug, Serialize)]
+struct LanguageIndex {
+    lang: String,
+    url: String,
+}
+
+/// A link to the page listing the posts by one author.
+#[derive(Clone, Debug, Serialize)]
+struct AuthorIndex {
+    name: String,
+    url: String,
+}
+
+/// A function the markdown of each post is passed through before it's rendered.
+pub type MarkdownTransform = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+#[derive(Clone)]
 pub struct BlogGenerator {
     base_url: String,
     css_source_file: String,
     markdown_sources_dir: String,
     rendered_outputs_dir: String,
+    bare: bool,
+    bare_keep_index: bool,
+    strict: bool,
+    resolve_css_imports: bool,
+    check_alt: bool,
+    check_headings: bool,
+    not_found_page: bool,
+    not_found_source: Option<String>,
+    heading_offset: usize,
+    index_excerpts: bool,
+    copy_images: bool,
+    inline_images_max_bytes: Option<u64>,
+    optimize_images: bool,
+    toc: bool,
+    toc_depth: usize,
+    number_headings: bool,
+    slug_transliterate: bool,
+    precompress: Option<Precompression>,
+    use_git_dates: bool,
+    progress: bool,
+    quiet: bool,
+    markdown_flavor: MarkdownFlavor,
+    extra_markdown_options: Options,
+    strict_commonmark: bool,
+    external_links_new_tab: bool,
+    relative_links: bool,
+    blockquote_cite: bool,
+    content_class: Option<String>,
+    data_attributes: bool,
+    write_retries: u32,
+    file_mode: Option<u32>,
+    build_lock: bool,
+    post_template: Option<String>,
+    index_template: Option<String>,
+    template_rules: Vec<TemplateRule>,
+    theme_static_dir: Option<PathBuf>,
+    csp_nonce: Option<String>,
+    publish_future: bool,
+    include_drafts: bool,
+    tag_cloud: bool,
+    date_format: String,
+    sanitize: bool,
+    disable_raw_html: bool,
+    smooth_scroll: bool,
+    scroll_offset: u32,
+    source_date_epoch: Option<DateTime<Utc>>,
+    max_file_size: u64,
+    source_encoding: Option<SourceEncoding>,
+    archive_layout: Option<ArchiveLayout>,
+    /// the subfolder every post goes in, with a trailing `/` (or empty)
+    posts_subdir: String,
+    since: Option<Since>,
+    disambiguate: bool,
+    post_order: PostOrder,
+    site_header: bool,
+    site_title: Option<String>,
+    etags: bool,
+    final_newline: bool,
+    bom: bool,
+    line_ending: Option<LineEnding>,
+    archive: bool,
+    bundle_file: Option<String>,
+    head_include: Option<String>,
+    body_end_include: Option<String>,
+    includes_on_index: bool,
+    code_copy_button: bool,
+    favicon: Option<String>,
+    feed_format: Option<FeedFormat>,
+    feed_content: FeedContent,
+    recent_count: Option<usize>,
+    blogroll: Option<Blogroll>,
+    long_read_threshold: usize,
+    index_title_max_chars: Option<usize>,
+    render_cache: Option<Arc<Mutex<RenderCache>>>,
+    cache_file: Option<PathBuf>,
+    markdown_transform: Option<MarkdownTransform>,
+    pdf_renderer: Option<String>,
+    content_width: Option<u32>,
+    font_scale: Option<f32>,
+    report_changes: bool,
+    link_refs: Option<String>,
+    partials_dir: Option<String>,
+    comment_metadata: bool,
+    figures: bool,
+    canonical_urls: bool,
+    critical_css: Option<String>,
+    print_css: Option<String>,
+    og_images: bool,
+    og_background: Option<PathBuf>,
+    og_font: Option<PathBuf>,
+    single_page: bool,
+    json_output: bool,
 }
 
 impl BlogGenerator {
@@ -70,223 +354,4150 @@ impl BlogGenerator {
         markdown_sources_dir: String,
         rendered_outputs_dir: String,
     ) -> Result<Self, BlogGeneratorError> {
-        let css_metadata = fs::metadata(&css_source_file);
-        if !css_metadata.is_ok() {
-            let e = css_metadata.unwrap_err();
-            return Err(BlogGeneratorError::InvalidCSSPath(
-                format!("{}", &css_source_file),
-                e.to_string(),
-            ));
-        }
-
-        let markdown_metadata = fs::metadata(&markdown_sources_dir);
-        if !markdown_metadata.is_ok() {
-            let e = markdown_metadata.unwrap_err();
+        if let Err(e) = fs::metadata(&markdown_sources_dir) {
             return Err(BlogGeneratorError::InvalidMarkDownPath(
-                format!("{}", &markdown_sources_dir),
+                markdown_sources_dir.clone(),
                 e.to_string(),
             ));
         }
 
-        let rendered_metadata = fs::metadata(&rendered_outputs_dir);
-        if !rendered_metadata.is_ok() {
-            let e = rendered_metadata.unwrap_err();
+        if let Err(e) = fs::metadata(&rendered_outputs_dir) {
             return Err(BlogGeneratorError::InvalidRenderedOutputPath(
-                format!("{}", &rendered_outputs_dir),
+                rendered_outputs_dir.clone(),
                 e.to_string(),
             ));
         }
 
+        let mut bg = BlogGenerator::new_standalone(base_url, css_source_file)?;
+        bg.markdown_sources_dir = markdown_sources_dir;
+        bg.rendered_outputs_dir = rendered_outputs_dir;
+
+        Ok(bg)
+    }
+
+    /// Creates a generator without markdown sources or an output directory, for use with
+    /// `render_str` only (`render` needs the directories set up by `new`).
+    pub fn new_standalone(
+        base_url: String,
+        css_source_file: String,
+    ) -> Result<Self, BlogGeneratorError> {
+        validate_css_source(&css_source_file)?;
+
         Ok(BlogGenerator {
             base_url,
             css_source_file,
-            markdown_sources_dir,
-            rendered_outputs_dir,
+            markdown_sources_dir: String::new(),
+            rendered_outputs_dir: String::new(),
+            bare: false,
+            bare_keep_index: false,
+            strict: false,
+            resolve_css_imports: false,
+            check_alt: false,
+            check_headings: false,
+            not_found_page: false,
+            not_found_source: None,
+            heading_offset: 0,
+            index_excerpts: false,
+            copy_images: false,
+            inline_images_max_bytes: None,
+            optimize_images: false,
+            toc: false,
+            toc_depth: 3,
+            number_headings: false,
+            slug_transliterate: false,
+            precompress: None,
+            use_git_dates: false,
+            progress: false,
+            quiet: false,
+            markdown_flavor: MarkdownFlavor::default(),
+            extra_markdown_options: Options::empty(),
+            strict_commonmark: false,
+            external_links_new_tab: false,
+            relative_links: false,
+            blockquote_cite: false,
+            content_class: None,
+            data_attributes: false,
+            write_retries: 0,
+            file_mode: None,
+            build_lock: true,
+            post_template: None,
+            index_template: None,
+            template_rules: Vec::new(),
+            theme_static_dir: None,
+            csp_nonce: None,
+            publish_future: false,
+            include_drafts: false,
+            tag_cloud: false,
+            date_format: "%Y-%m-%d".to_string(),
+            sanitize: false,
+            disable_raw_html: false,
+            smooth_scroll: false,
+            scroll_offset: 0,
+            source_date_epoch: None,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            source_encoding: None,
+            archive_layout: None,
+            posts_subdir: String::new(),
+            since: None,
+            disambiguate: false,
+            post_order: PostOrder::default(),
+            site_header: true,
+            site_title: None,
+            etags: false,
+            final_newline: false,
+            bom: false,
+            line_ending: None,
+            archive: false,
+            bundle_file: None,
+            head_include: None,
+            body_end_include: None,
+            includes_on_index: false,
+            code_copy_button: false,
+            favicon: None,
+            feed_format: None,
+            feed_content: FeedContent::default(),
+            recent_count: None,
+            blogroll: None,
+            long_read_threshold: DEFAULT_LONG_READ_THRESHOLD,
+            index_title_max_chars: None,
+            render_cache: None,
+            markdown_transform: None,
+            cache_file: None,
+            pdf_renderer: None,
+            content_width: None,
+            font_scale: None,
+            report_changes: false,
+            link_refs: None,
+            partials_dir: None,
+            comment_metadata: false,
+            figures: false,
+            canonical_urls: false,
+            critical_css: None,
+            print_css: None,
+            og_images: false,
+            og_background: None,
+            og_font: None,
+            single_page: false,
+            json_output: false,
         })
     }
 
-    pub fn render(&self) -> Result<(), BlogGeneratorError> {
-        let mut css_from_source = String::new();
-        let css_f = File::open(&self.css_source_file);
-        match css_f {
-            Ok(mut css_f) => {
-                let _ = css_f.read_to_string(&mut css_from_source).map_err(|e| {
-                    BlogGeneratorError::CSSSourceError(
-                        format!("{}", &self.css_source_file),
-                        e.to_string(),
-                    )
-                });
-                //println!("{:?}", &css_from_source);
-            }
-            Err(e) => {
-                return Err(BlogGeneratorError::InvalidCSSPath(
-                    format!("{}", &self.css_source_file),
-                    e.to_string(),
-                ))
-            }
-        }
+    /// In bare mode each post is written as just its rendered markdown fragment,
+    /// skipping the HTML template (and its styles) entirely.
+    /// The index page is skipped as well unless `keep_index` is set.
+    pub fn with_bare(mut self, bare: bool, keep_index: bool) -> Self {
+        self.bare = bare;
+        self.bare_keep_index = keep_index;
+        self
+    }
 
-        let mut markdown_files: Vec<MarkDownFile> = Vec::new();
-        let md_glob_path = format!("{}/{}", &self.markdown_sources_dir, "*.md");
+    /// In strict mode, problems that are otherwise only warned about
+    /// (such as duplicate post titles) fail the render instead.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
 
-        // for each markdown file, append to a vec of MarkDownFile type
-        // later sort by creation date
-        for entry in glob(&md_glob_path).expect("failed to read any markdown source files") {
-            match entry {
-                Ok(path) => {
-                    //let file_path = format!("{:?}", &path.display());
-                    //println!("{:?}", &file_path);
+    /// Inline the contents of any local `@import`ed stylesheets into the CSS source
+    /// before it gets embedded, since the imports would otherwise not resolve relative to
+    /// the rendered output.
+    pub fn with_resolve_css_imports(mut self, resolve_css_imports: bool) -> Self {
+        self.resolve_css_imports = resolve_css_imports;
+        self
+    }
 
-                    let f = File::open(&path).map_err(|e| {
-                        BlogGeneratorError::MarkDownFileError(
-                            format!("{}", &path.display()),
-                            e.to_string(),
-                        )
-                    });
+    /// Warn about (or under strict mode, fail on) images in the rendered posts that have no
+    /// `alt` attribute. Images with an empty `alt=""` are considered decorative and allowed.
+    pub fn with_check_alt(mut self, check_alt: bool) -> Self {
+        self.check_alt = check_alt;
+        self
+    }
 
-                    let f_metadata = f.unwrap().metadata().map_err(|e| {
-                        BlogGeneratorError::MarkDownMetadataError(
-                            format!("{}", &path.display()),
-                            e.to_string(),
-                        )
-                    });
+    /// Warn about (or under strict mode, fail on) posts whose headings skip a level (an
+    /// `<h2>` followed directly by an `<h4>`), or that don't have exactly one title heading.
+    pub fn with_check_headings(mut self, check_headings: bool) -> Self {
+        self.check_headings = check_headings;
+        self
+    }
 
-                    let created_at = f_metadata.unwrap().created().unwrap();
-                    let created_time: DateTime<Utc> = created_at.into();
+    /// Also generate a `404.html` page for static hosts that serve one for missing pages.
+    /// Its content comes from the `source` markdown file when given, and a generic
+    /// "page not found" message otherwise.
+    pub fn with_not_found_page(mut self, not_found_page: bool, source: Option<String>) -> Self {
+        self.not_found_page = not_found_page || source.is_some();
+        self.not_found_source = source;
+        self
+    }
 
-                    let mdf = MarkDownFile {
-                        file_name: PathBuf::from(&path.file_name().unwrap()),
-                        file_path_buf: path.clone(),
-                        created_time,
-                        title_from_md: None,
-                    };
+    /// Shift every heading in the rendered posts down by `heading_offset` levels
+    /// (so with an offset of 1 a `#` heading becomes an `<h2>`), clamping at `<h6>`.
+    /// Useful when the posts get embedded in a page that has its own `<h1>`.
+    pub fn with_heading_offset(mut self, heading_offset: usize) -> Self {
+        self.heading_offset = heading_offset;
+        self
+    }
 
-                    markdown_files.push(mdf);
-                }
-                Err(e) => {
-                    println!("{:?}", e);
-                    continue;
+    /// Show an excerpt of each post under its link on the index page. The excerpt is
+    /// everything above a `<!-- more -->` marker in the markdown source, or the first
+    /// paragraph of the post when there's no marker.
+    pub fn with_index_excerpts(mut self, index_excerpts: bool) -> Self {
+        self.index_excerpts = index_excerpts;
+        self
+    }
+
+    /// Copy the local images referenced by each post (relative to the markdown sources dir)
+    /// into the same relative location in the output dir.
+    /// With `optimize` set, a WebP version of each copied image is generated as well and the
+    /// `<img>` tags are wrapped in a `<picture>` offering it, with the original as fallback.
+    pub fn with_copy_images(mut self, copy_images: bool, optimize: bool) -> Self {
+        self.copy_images = copy_images || optimize;
+        self.optimize_images = optimize;
+        self
+    }
+
+    /// Embed the local images of each post that are no bigger than `max_bytes` in it as
+    /// `data:` uris, rather than referring to (or copying) their files.
+    pub fn with_inline_small_images(mut self, max_bytes: Option<u64>) -> Self {
+        self.inline_images_max_bytes = max_bytes;
+        self
+    }
+
+    fn copy_local_images(
+        &self,
+        body_content: &str,
+        copied_images: &mut HashMap<String, bool>,
+        summary: &mut BuildSummary,
+    ) -> String {
+        for src in local_image_sources(body_content) {
+            if copied_images.contains_key(&src) {
+                continue;
+            }
+
+            let mut has_webp = false;
+            let copied = self.copy_asset(&src);
+            match copied {
+                Ok(copied) => {
+                    summary.images_copied = summary.images_copied.map(|count| count + 1);
+                    if self.optimize_images {
+                        let converted = convert_to_webp(&copied).and_then(|webp| match &webp {
+                            Some(webp_path) => self
+                                .apply_file_mode(webp_path)
+                                .map_err(|e| e.to_string())
+                                .map(|_| webp),
+                            None => Ok(webp),
+                        });
+                        match converted {
+                            Ok(Some(_)) => {
+                                has_webp = true;
+                                summary.webp_images_generated =
+                                    summary.webp_images_generated.map(|count| count + 1);
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!("could not generate a webp version of {}: {}", src, e),
+                        }
+                    }
                 }
+                Err(e) => warn!("{}", e),
             }
+            copied_images.insert(src, has_webp);
         }
 
-        // sort the vector of markdown files by created date
-        let mut markdown_files_sorted = markdown_files.clone();
-        markdown_files_sorted.sort_by(|a, b| a.created_time.cmp(&b.created_time));
-
-        let mut tera = Tera::default();
-        let _ = tera
-            .add_raw_template("html", get_html_template())
-            .map_err(|e| BlogGeneratorError::TemplateAddError("html".to_string(), e.to_string()));
+        if !self.optimize_images {
+            return body_content.to_string();
+        }
 
-        let mut pages: Vec<Page> = Vec::new();
+        rewrite_images_as_pictures(body_content, |src| copied_images.get(src) == Some(&true))
+    }
 
-        for mdf in markdown_files_sorted.iter_mut() {
-            let mut md_content = String::new();
-            let markdown_f = File::open(&*mdf.file_path_buf).map_err(|e| {
-                BlogGeneratorError::InvalidMarkDownPath(
-                    format!("{}", &mdf.file_path_buf.display()),
-                    e.to_string(),
-                )
-            });
+    /// Copies a local asset of a post to the rendered outputs dir, returning its path there.
+    fn copy_asset(&self, src: &str) -> Result<PathBuf, BlogGeneratorError> {
+        let copied = copy_asset(&self.markdown_sources_dir, &self.rendered_outputs_dir, src)?;
+        self.apply_file_mode(&copied)
+            .map_err(|e| BlogGeneratorError::AssetError(src.to_string(), e.to_string()))?;
+        Ok(copied)
+    }
 
-            if let Ok(mut markdown_f) = markdown_f {
-                let _ = markdown_f.read_to_string(&mut md_content).map_err(|e| {
-                    BlogGeneratorError::MarkDownFileError(
-                        format!("{}", &mdf.file_path_buf.display()),
-                        e.to_string(),
-                    )
-                });
+    /// Copies the local video and audio files (and video posters) of a post to the rendered
+    /// outputs dir, noting any that can't be copied as a problem with the post.
+    fn copy_local_media(
+        &self,
+        html: &str,
+        source: &str,
+        copied: &mut HashMap<String, bool>,
+        summary: &mut BuildSummary,
+    ) {
+        for src in local_media_sources(html) {
+            if copied.contains_key(&src) {
+                continue;
             }
 
-            let mut options = Options::empty();
-            options.insert(Options::ENABLE_STRIKETHROUGH);
-            options.insert(Options::ENABLE_TABLES);
-            options.insert(Options::ENABLE_FOOTNOTES);
-            options.insert(Options::ENABLE_TASKLISTS);
-            options.insert(Options::ENABLE_SMART_PUNCTUATION);
+            if let Err(e) = self.copy_asset(&src) {
+                warn!("{}", e);
+                summary
+                    .problems
+                    .add(source, None, format!("could not copy {}: {}", src, e));
+            }
+            copied.insert(src, false);
+        }
+    }
 
-            let parser = Parser::new_ext(&md_content, options);
-            let mut body_content = String::new();
-            html::push_html(&mut body_content, parser);
+    /// The html of a post with its captioned images numbered as figures. References to
+    /// figures that don't exist are noted as problems with the post.
+    fn number_figures(
+        &self,
+        mdf: &MarkDownFile,
+        md_content: &str,
+        body_content: &str,
+        summary: &mut BuildSummary,
+    ) -> String {
+        let (body_content, unresolved) = number_figures(body_content);
 
-            // also try and scrape out the title from the markdown file
-            let fragment = Html::parse_fragment(&body_content);
-            if let Ok(selector) = Selector::parse("h1") {
-                let h1 = fragment.select(&selector).next().unwrap();
-                let title_text: Vec<&str> = h1.text().collect::<Vec<_>>();
-                let title_text = format!("{:?}", title_text[0]);
-                println!("Entry title: {:?}", &title_text);
-                mdf.title_from_md = Some(title_text.clone());
-            };
+        let source = format!("{}", mdf.file_path_buf.display());
+        for reference in unresolved {
+            warn!(
+                "{} refers to a figure that doesn't exist: {}",
+                source, reference
+            );
+            summary.problems.add(
+                &source,
+                line_of(md_content, &reference).map(|line| line + mdf.first_body_line - 1),
+                format!("no figure for the reference {}", reference),
+            );
+        }
 
-            // render the template
-            let mut context = tera::Context::new();
-            context.insert("body_content", &body_content);
-            context.insert("css_from_source", &css_from_source);
+        body_content
+    }
 
-            let rendered = tera.render("html", &context);
-            if let Ok(rendered) = rendered {
-                //println!("{:?}", &rendered);
-                let title = &*mdf.title_from_md.clone().unwrap();
-                let out_file_name = &*mdf.file_name.to_str().unwrap().replace(".md", ".html");
-                let out_file_name: String = out_file_name.to_string();
+    /// The markdown of a post with its shortcodes expanded. Unknown shortcodes are passed
+    /// through as they are, and noted as a problem with the post.
+    fn expand_shortcodes(&self, mdf: &MarkDownFile, summary: &mut BuildSummary) -> String {
+        let (md_content, unexpanded) = expand_shortcodes(&mdf.md_content);
 
-                let out_path = format!("{}/{}", &self.rendered_outputs_dir, &out_file_name);
+        let source = format!("{}", mdf.file_path_buf.display());
+        for (shortcode, problem) in unexpanded {
+            warn!(
+                "{} has a shortcode that can't be expanded ({}): {}",
+                source, problem, shortcode
+            );
+            summary.problems.add(
+                &source,
+                line_of(&mdf.md_content, &shortcode).map(|line| line + mdf.first_body_line - 1),
+                format!("{}: {}", problem, shortcode),
+            );
+        }
 
-                let f = File::create(&out_path).map_err(|e| {
-                    BlogGeneratorError::FileWriteError(out_path.to_string(), e.to_string())
-                });
+        md_content
+    }
 
-                let f_write = f.unwrap().write_all(&rendered.as_bytes());
-                match f_write {
-                    Ok(_f) => {
-                        println!("wrote {:?}", &out_path);
-                        let page = Page {
-                            title: title.to_string().replace("\"", ""),
-                            url: format!("{}{}", &self.base_url, out_file_name.clone()),
-                        };
-                        pages.push(page);
-                    }
-                    Err(e) => {
-                        println!("error writing rendered file {:?}: {}", &out_path, e);
-                        continue;
-                    }
-                }
-            } else {
-                return Err(BlogGeneratorError::TemplateUseError(
-                    "html".to_string(),
-                    "".to_string(),
+    /// Notes the headings of a post that are out of place in its hierarchy of headings,
+    /// failing on the first one in strict mode.
+    fn check_headings(
+        &self,
+        mdf: &MarkDownFile,
+        md_content: &str,
+        html: &str,
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let source = format!("{}", mdf.file_path_buf.display());
+        let title_level = self.offset_heading_level(HeadingLevel::H1) as usize;
+        for problem in check_heading_hierarchy(html, title_level) {
+            if self.strict {
+                return Err(BlogGeneratorError::HeadingHierarchy(
+                    source,
+                    problem.to_string(),
                 ));
             }
+            warn!("{} has a heading out of place: {}", source, problem);
+            let line = match &problem {
+                HeadingProblem::SkippedLevel { to, .. } => {
+                    line_of(md_content, &to.1).map(|line| line + mdf.first_body_line - 1)
+                }
+                HeadingProblem::TitleCount(..) => None,
+            };
+            summary.problems.add(&source, line, problem.to_string());
         }
 
-        // generate an index page that contains links to all the pages, sorted by creation time
-        let _ = tera
-            .add_raw_template("index", get_index_page_template())
-            .map_err(|e| BlogGeneratorError::TemplateAddError("index".to_string(), e.to_string()));
+        Ok(())
+    }
 
-        let mut context = tera::Context::new();
-        context.insert("pages", &pages);
+    /// Warns about the markdown of a post that wouldn't render as plain CommonMark.
+    fn check_commonmark(&self, mdf: &MarkDownFile, md_content: &str, summary: &mut BuildSummary) {
+        let source = format!("{}", mdf.file_path_buf.display());
+        let mut found: Vec<(usize, &str)> = Vec::new();
+        for (offset, construct) in find_extension_constructs(md_content) {
+            let line = md_content[..offset].matches('\n').count() + mdf.first_body_line;
+            if found.contains(&(line, construct)) {
+                continue;
+            }
+            found.push((line, construct));
 
-        let rendered = tera.render("index", &context);
-        if let Ok(rendered) = rendered {
-            let out_file = format!("{}/index.html", &self.rendered_outputs_dir);
+            warn!(
+                "{} line {} relies on a markdown extension: {}",
+                source, line, construct
+            );
+            summary.problems.add(
+                &source,
+                Some(line),
+                format!(
+                    "relies on a markdown extension, so renders differently elsewhere: {}",
+                    construct
+                ),
+            );
+        }
+    }
 
-            let f = File::create(&out_file).map_err(|e| {
-                BlogGeneratorError::FileWriteError(out_file.to_string(), e.to_string())
-            });
+    /// Add a table of contents after each post's title, linking to the post's headings
+    /// (which get ids generated from their text). Headings deeper than `depth` (a heading
+    /// level from 1 to 6) are left out of the table of contents.
+    pub fn with_toc(mut self, toc: bool, depth: usize) -> Self {
+        self.toc = toc;
+        self.toc_depth = depth;
+        self
+    }
 
-            let _ = f.unwrap().write_all(&rendered.as_bytes()).map_err(|e| {
-                BlogGeneratorError::FileWriteError(out_file.to_string(), e.to_string())
-            });
+    /// Number the headings below each post's title (`1`, `1.1`, `1.2`, `2`, ...), in the
+    /// post and its table of contents. A post can opt out (or in) with `number_headings`
+    /// in its front matter.
+    pub fn with_number_headings(mut self, number_headings: bool) -> Self {
+        self.number_headings = number_headings;
+        self
+    }
+
+    /// Transliterate the accented and non-Latin letters in the slugs made from titles, tags,
+    /// authors and headings to plain ASCII, rather than keeping them as they are (to be
+    /// percent-encoded in urls). Characters that can't be are replaced by a short hash.
+    pub fn with_slug_transliterate(mut self, slug_transliterate: bool) -> Self {
+        self.slug_transliterate = slug_transliterate;
+        self
+    }
+
+    /// The slug of a title, tag, author or series.
+    fn slug(&self, text: &str) -> String {
+        if self.slug_transliterate {
+            transliterated_slugify(text)
         } else {
-            return Err(BlogGeneratorError::TemplateUseError(
-                "index".to_string(),
-                "".to_string(),
-            ));
+            slugify(text)
+        }
+    }
+
+    /// Write `.gz` and/or `.br` compressed copies alongside every generated html file.
+    pub fn with_precompress(mut self, precompress: Option<Precompression>) -> Self {
+        self.precompress = precompress;
+        self
+    }
+
+    /// Date the posts by their git history (first and last commit) rather than by the
+    /// filesystem, and show a "last updated" footer on each post. Files that aren't tracked
+    /// by git still fall back to their filesystem times.
+    pub fn with_git_dates(mut self, use_git_dates: bool) -> Self {
+        self.use_git_dates = use_git_dates;
+        self
+    }
+
+    /// Show a progress bar while the posts are rendered. The bar is only drawn when stderr
+    /// is a terminal, and never in quiet mode.
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Don't print the usual status messages (the titles found and files written).
+    /// Warnings and errors are still reported.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Choose the markdown extensions to render posts with, from a preset `flavor` along
+    /// with any `extra_options` enabled on top of it.
+    pub fn with_markdown_flavor(mut self, flavor: MarkdownFlavor, extra_options: Options) -> Self {
+        self.markdown_flavor = flavor;
+        self.extra_markdown_options = extra_options;
+        self
+    }
+
+    /// Render the posts as plain CommonMark whatever the flavor and extensions, warning
+    /// about the markdown in them that relies on an extension, so that posts can be kept
+    /// rendering the same on any CommonMark renderer.
+    pub fn with_strict_commonmark(mut self, strict_commonmark: bool) -> Self {
+        self.strict_commonmark = strict_commonmark;
+        self
+    }
+
+    /// Turn a last line of `— Name` in a blockquote into a `<footer><cite>` attributing
+    /// the quote to Name.
+    pub fn with_blockquote_cite(mut self, blockquote_cite: bool) -> Self {
+        self.blockquote_cite = blockquote_cite;
+        self
+    }
+
+    /// Make links to other sites open in a new tab (with `rel="noopener noreferrer"`).
+    /// Links within the site, anchors and non-http links like `mailto:` are left alone.
+    pub fn with_external_links_new_tab(mut self, external_links_new_tab: bool) -> Self {
+        self.external_links_new_tab = external_links_new_tab;
+        self
+    }
+
+    /// Rewrite the links in the posts to pages and files of the site (by the full url of
+    /// an absolute base url, or the path from the root of its host) to be relative to each
+    /// post, so that the rendered outputs can be served from any path.
+    pub fn with_relative_links(mut self, relative_links: bool) -> Self {
+        self.relative_links = relative_links;
+        self
+    }
+
+    /// Set a class on the `<article>` element wrapping each post's content, for themes.
+    pub fn with_content_class(mut self, content_class: Option<String>) -> Self {
+        self.content_class = content_class;
+        self
+    }
+
+    /// Put the title, date (RFC 3339), tags (comma separated) and slug of each post in
+    /// `data-` attributes on its `<article>`, for scripts on the page to read.
+    pub fn with_data_attributes(mut self, data_attributes: bool) -> Self {
+        self.data_attributes = data_attributes;
+        self
+    }
+
+    /// The `data-` attributes of a post's `<article>`, escaped and ready to go in the tag.
+    fn data_attributes(&self, mdf: &MarkDownFile, title: &str) -> String {
+        let output_path = mdf.output_path();
+        let slug = output_path
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".html");
+        [
+            ("title", title.to_string()),
+            ("date", mdf.created_time.to_rfc3339()),
+            ("tags", mdf.front_matter.tags.join(",")),
+            ("slug", slug.to_string()),
+        ]
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "data-{}=\"{}\"",
+                name,
+                html_escape::encode_double_quoted_attribute(value)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+
+    /// Use the tera templates in the given files for posts and the index page in place
+    /// of the built-in ones. The same variables are available to custom templates.
+    pub fn with_templates(
+        mut self,
+        post_template: Option<String>,
+        index_template: Option<String>,
+    ) -> Self {
+        self.post_template = post_template;
+        self.index_template = index_template;
+        self
+    }
+
+    /// Use the template file of the first of the `rules` whose pattern matches a post's file
+    /// name for that post, in place of the post template. The same variables are available
+    /// to them as to the post template.
+    pub fn with_template_rules(mut self, rules: Vec<TemplateRule>) -> Self {
+        self.template_rules = rules;
+        self
+    }
+
+    /// Apply a theme's templates (unless already set by `with_templates`) and copy its
+    /// static files into the rendered outputs dir. The theme's stylesheet isn't picked up
+    /// here, it is expected to be passed to `new` when no other css source is given.
+    pub fn with_theme(mut self, theme: Option<Theme>) -> Self {
+        if let Some(theme) = theme {
+            self.post_template = self
+                .post_template
+                .or(Some(format!("{}", theme.post_template_file.display())));
+            self.index_template = self.index_template.or(theme
+                .index_template_file
+                .map(|p| format!("{}", p.display())));
+            self.theme_static_dir = theme.static_dir;
         }
+        self
+    }
+
+    /// Add a `nonce` attribute to the inline `<style>` of each page, so the styles are
+    /// allowed by a Content-Security-Policy with a matching `style-src 'nonce-...'`.
+    pub fn with_csp_nonce(mut self, csp_nonce: Option<String>) -> Self {
+        self.csp_nonce = csp_nonce;
+        self
+    }
+
+    /// Posts with a front matter `date` in the future are skipped (and reported in the
+    /// build summary) unless `publish_future` is set.
+    pub fn with_publish_future(mut self, publish_future: bool) -> Self {
+        self.publish_future = publish_future;
+        self
+    }
+
+    /// Posts marked `draft: true` in their front matter are skipped (and reported in the
+    /// build summary) unless `include_drafts` is set.
+    pub fn with_drafts(mut self, include_drafts: bool) -> Self {
+        self.include_drafts = include_drafts;
+        self
+    }
+
+    /// Show a cloud of all the tags on the index page, sized by how many posts carry them.
+    pub fn with_tag_cloud(mut self, tag_cloud: bool) -> Self {
+        self.tag_cloud = tag_cloud;
+        self
+    }
+
+    /// The `strftime` style format of the dates shown on the index and on the posts.
+    pub fn with_date_format(mut self, date_format: String) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    /// Run the rendered html of each post through an allowlist sanitizer, for markdown
+    /// from untrusted contributors. Off by default, since raw html in trusted markdown
+    /// (embeds, custom markup) would be stripped as well.
+    pub fn with_sanitize(mut self, sanitize: bool) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Drop the raw html blocks and inline html from the markdown while it's parsed, so
+    /// that only the html generated from markdown syntax ends up in the posts. Unlike
+    /// `with_sanitize`, this leaves attributes and urls of the generated tags alone. Posts
+    /// with `allow_html: true` in their front matter keep their raw html.
+    pub fn with_disable_raw_html(mut self, disable_raw_html: bool) -> Self {
+        self.disable_raw_html = disable_raw_html;
+        self
+    }
+
+    /// Scroll smoothly to the headings linked from the table of contents, stopping
+    /// `scroll_offset` pixels above them (to clear a fixed header, say).
+    pub fn with_smooth_scroll(mut self, smooth_scroll: bool, scroll_offset: u32) -> Self {
+        self.smooth_scroll = smooth_scroll;
+        self.scroll_offset = scroll_offset;
+        self
+    }
+
+    /// For reproducible builds: use this time as the build time, and as the date of posts
+    /// that have neither a front matter date nor (with git dates) any commit history.
+    pub fn with_source_date_epoch(mut self, source_date_epoch: Option<DateTime<Utc>>) -> Self {
+        self.source_date_epoch = source_date_epoch;
+        self
+    }
+
+    /// Markdown source files bigger than `max_file_size` bytes are skipped with a warning.
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Decode the markdown source files that aren't valid UTF-8 from `source_encoding`,
+    /// rather than skipping them.
+    pub fn with_source_encoding(mut self, source_encoding: Option<SourceEncoding>) -> Self {
+        self.source_encoding = source_encoding;
+        self
+    }
+
+    /// Order the posts by the `order` in their front matter instead of by date (see
+    /// `PostOrder`), for hand ordered sites such as documentation.
+    pub fn with_post_order(mut self, post_order: PostOrder) -> Self {
+        self.post_order = post_order;
+        self
+    }
+
+    /// Write the posts into date based subfolders (`2024/03/post.html`) rather than
+    /// straight into the rendered outputs dir.
+    pub fn with_archive_layout(mut self, archive_layout: Option<ArchiveLayout>) -> Self {
+        self.archive_layout = archive_layout;
+        self
+    }
+
+    /// Write the posts into `posts_subdir` (like `posts`), leaving the index, feeds and
+    /// the other listing pages at the root of the rendered outputs dir.
+    pub fn with_posts_subdir(mut self, posts_subdir: Option<String>) -> Self {
+        self.posts_subdir = posts_subdir
+            .map(|dir| format!("{}/", dir.trim_matches('/')))
+            .unwrap_or_default();
+        self
+    }
+
+    /// Only build the posts dated on or after `since` (a date, or a time before the build),
+    /// leaving the older ones out of the output and the index.
+    pub fn with_since(mut self, since: Option<Since>) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// When two posts would be published at the same url, give the newer one a numbered
+    /// suffix (`post-2.html`) rather than failing the build.
+    pub fn with_disambiguate(mut self, disambiguate: bool) -> Self {
+        self.disambiguate = disambiguate;
+        self
+    }
+
+    /// Show a header linking back to the index at the top of every post, with the
+    /// `site_title` (or just "Home") as the link text.
+    pub fn with_site_header(mut self, site_header: bool, site_title: Option<String>) -> Self {
+        self.site_header = site_header;
+        self.site_title = site_title;
+        self
+    }
+
+    /// Write an `etags.json` mapping the path of each generated page to the sha256 of its
+    /// contents, for a reverse proxy or CDN to serve as `ETag` headers.
+    pub fn with_etags(mut self, etags: bool) -> Self {
+        self.etags = etags;
+        self
+    }
+
+    /// Make every output file end with exactly one newline, and/or convert all of its line
+    /// endings to `line_ending`, and/or start it with a UTF-8 byte order mark (`bom`, left
+    /// out of the json files, which mustn't have one). Files are written just as the
+    /// templates produce them otherwise.
+    pub fn with_output_format(
+        mut self,
+        final_newline: bool,
+        line_ending: Option<LineEnding>,
+        bom: bool,
+    ) -> Self {
+        self.final_newline = final_newline;
+        self.line_ending = line_ending;
+        self.bom = bom;
+        self
+    }
+
+    /// Also generate an `archive.html` listing every post by year and month, linked
+    /// from the index.
+    pub fn with_archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Read the posts from a single bundle file instead of the markdown sources dir, which
+    /// is still where local images are copied from.
+    pub fn with_bundle(mut self, bundle_file: Option<String>) -> Self {
+        self.bundle_file = bundle_file;
+        self
+    }
+
+    /// Include snippets of html (analytics scripts, say) verbatim at the end of the
+    /// `<head>` and of the `<body>` of every post, and of the index pages as well when
+    /// `on_index` is set. The snippets are available to templates as `head_extra` and
+    /// `body_end_extra`.
+    pub fn with_includes(
+        mut self,
+        head_include: Option<String>,
+        body_end_include: Option<String>,
+        on_index: bool,
+    ) -> Self {
+        self.head_include = head_include;
+        self.body_end_include = body_end_include;
+        self.includes_on_index = on_index;
+        self
+    }
+
+    /// Give each code block a button that copies its contents to the clipboard. The
+    /// script for the buttons is only added to posts that have code blocks.
+    pub fn with_code_copy_button(mut self, code_copy_button: bool) -> Self {
+        self.code_copy_button = code_copy_button;
+        self
+    }
+
+    /// Copy the favicon (a `.ico`, `.png` or `.svg`) into the rendered outputs dir and link
+    /// it from every page, as the touch icon as well when it is a png.
+    pub fn with_favicon(mut self, favicon: Option<String>) -> Self {
+        self.favicon = favicon;
+        self
+    }
+
+    /// Also generate RSS, Atom and/or JSON feeds of the posts, with `feed_content` (the whole
+    /// post, its excerpt or nothing) under each title. Feed readers need absolute links,
+    /// so the base url should be the full url of the site.
+    pub fn with_feeds(
+        mut self,
+        feed_format: Option<FeedFormat>,
+        feed_content: FeedContent,
+    ) -> Self {
+        self.feed_format = feed_format;
+        self.feed_content = feed_content;
+        self
+    }
+
+    /// Also write a `recent.html` fragment listing the `recent_count` most recent posts,
+    /// for embedding in other sites. Its links are only absolute with a full base url.
+    pub fn with_recent_posts(mut self, recent_count: Option<usize>) -> Self {
+        self.recent_count = recent_count;
+        self
+    }
+
+    /// Also write the `blogroll` out as `blogroll.opml`, for feed readers, and as a
+    /// `blogroll.html` page linking to each of the blogs.
+    pub fn with_blogroll(mut self, blogroll: Option<Blogroll>) -> Self {
+        self.blogroll = blogroll;
+        self
+    }
+
+    /// Flag the posts with more than `long_read_threshold` words as long reads, for the
+    /// templates to mark (as `long_read`, along with the post's `word_count`).
+    pub fn with_long_read_threshold(mut self, long_read_threshold: usize) -> Self {
+        self.long_read_threshold = long_read_threshold;
+        self
+    }
+
+    /// Shorten the titles in the index (and the other listings) to `max_chars` characters,
+    /// with the full title in the link's tooltip.
+    pub fn with_index_title_max_chars(mut self, max_chars: Option<usize>) -> Self {
+        self.index_title_max_chars = max_chars;
+        self
+    }
+
+    /// Give the pages a maximum content width (in px) and scale their base font size, for
+    /// a readable layout without writing any css. These come before the css source, so
+    /// the css can still override them.
+    pub fn with_layout(mut self, content_width: Option<u32>, font_scale: Option<f32>) -> Self {
+        self.content_width = content_width;
+        self.font_scale = font_scale;
+        self
+    }
+
+    /// Generate an Open Graph image (`og/<post>.png`) for each post, showing its title and
+    /// the site title on `background` (if given), and link it with an `og:image` meta tag.
+    /// Posts with an `image` in their front matter use that instead. Social sites need an
+    /// absolute url for the image, so the base url should be the full url of the site.
+    pub fn with_og_images(mut self, og_images: bool, background: Option<PathBuf>) -> Self {
+        self.og_images = og_images;
+        self.og_background = background;
+        self
+    }
+
+    /// The TrueType or OpenType font to draw the text of the Open Graph images in, in place
+    /// of the first of the common system fonts (DejaVu Sans, Liberation Sans, Arial) found.
+    pub fn with_og_font(mut self, og_font: Option<PathBuf>) -> Self {
+        self.og_font = og_font;
+        self
+    }
+
+    /// Also write every post onto one page, `all.html`, in the same order as the index and
+    /// with a table of contents at the top, for printing or reading offline.
+    pub fn with_single_page(mut self, single_page: bool) -> Self {
+        self.single_page = single_page;
+        self
+    }
+
+    /// Also write each post's title, dates, tags, excerpt and rendered html to a `.json`
+    /// file next to its page, with a `posts.json` listing them all (see `JsonPost` for
+    /// what's in them), for frontends that do their own templating. A post whose `.json`
+    /// would take the name of one of the generator's own files, like `posts.md`, is
+    /// reported rather than written.
+    pub fn with_json_output(mut self, json_output: bool) -> Self {
+        self.json_output = json_output;
+        self
+    }
+
+    /// Writes `all.html`, with the posts one after the other in a section each. The ids in
+    /// each post are prefixed with the id of its section, so that they don't collide.
+    fn render_single_page(
+        &self,
+        tera: &SiteTemplates,
+        css_from_source: &str,
+        pages: &[Page],
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let posts: Vec<SinglePagePost> = pages
+            .iter()
+            .map(|page| {
+                let id = format!(
+                    "post-{}",
+                    self.slug(&page.path.trim_end_matches(".html").replace('/', "-"))
+                );
+                SinglePagePost {
+                    html: prefix_ids(&rebase_links(&page.body_html, &page.path), &id),
+                    id,
+                    title: page.title.clone(),
+                    date: page.date.clone(),
+                }
+            })
+            .collect();
+        let heading = self.site_title.as_deref().unwrap_or("All posts");
+        let body_content = single_page_html(heading, &posts);
+
+        let context = self.page_context(&body_content, css_from_source, 0);
+        let rendered = tera
+            .render("html", &context)
+            .map_err(|e| BlogGeneratorError::TemplateUseError("html".to_string(), e.to_string()))?;
+
+        let out_file = format!("{}/all.html", &self.rendered_outputs_dir);
+        self.write_output(&out_file, &rendered, summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
 
         Ok(())
     }
+
+    /// Writes the Open Graph image of a post, returning its url (or `None`, noting the
+    /// problem, when it couldn't be written). With `keep_existing`, an image already
+    /// written is used as it is.
+    fn write_og_image(
+        &self,
+        mdf: &MarkDownFile,
+        title: &str,
+        background: Option<&DynamicImage>,
+        font: &FontVec,
+        keep_existing: bool,
+        summary: &mut BuildSummary,
+    ) -> Option<String> {
+        let path = format!("og/{}", mdf.output_path().replace(".html", ".png"));
+        let out_file = Path::new(&self.rendered_outputs_dir).join(&path);
+        if keep_existing && out_file.is_file() {
+            return Some(format!("{}{}", &self.base_url, path));
+        }
+
+        let image = og_image(title, self.site_title.as_deref(), background, font);
+        let written = out_file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| image.save(&out_file).map_err(|e| e.to_string()))
+            .and_then(|_| self.apply_file_mode(&out_file).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("could not write {}: {}", out_file.display(), e);
+            summary.problems.add(
+                &format!("{}", mdf.file_path_buf.display()),
+                None,
+                format!("could not write its Open Graph image: {}", e),
+            );
+            return None;
+        }
+
+        summary.og_images_generated = summary.og_images_generated.map(|count| count + 1);
+        Some(format!("{}{}", &self.base_url, path))
+    }
+
+    /// Add print styles to the posts, from the `print_css` rules (wrapped in an
+    /// `@media print` after the rest of the css) or else the default ones, which leave
+    /// out the navigation and print the urls of links.
+    pub fn with_print_styles(mut self, print_styles: bool, print_css: Option<String>) -> Self {
+        self.print_css =
+            print_styles.then(|| print_css.unwrap_or_else(|| get_print_css().to_string()));
+        self
+    }
+
+    /// Inline only the `critical_css` in each page, writing the full css source out to
+    /// `site.css` to be loaded without blocking the page from rendering. The stylesheet is
+    /// swapped in by an inline `onload` handler, or with a csp nonce by a script carrying
+    /// it (since a Content-Security-Policy blocks inline handlers).
+    pub fn with_critical_css(mut self, critical_css: Option<String>) -> Self {
+        self.critical_css = critical_css;
+        self
+    }
+
+    /// Give each post a `<link rel="canonical">` to its own url (under the base url, which
+    /// should be absolute). A `canonical` url in a post's front matter, for a post first
+    /// published elsewhere, is used whether or not this is set.
+    pub fn with_canonical_urls(mut self, canonical_urls: bool) -> Self {
+        self.canonical_urls = canonical_urls;
+        self
+    }
+
+    /// Turn images captioned by the line after them into numbered figures, which the
+    /// post can refer to by label (see `figures::number_figures`).
+    pub fn with_figures(mut self, figures: bool) -> Self {
+        self.figures = figures;
+        self
+    }
+
+    /// Reference-style link definitions (`[rust]: https://www.rust-lang.org`) shared by every
+    /// post. They're added after each post's markdown, and since the first definition of a
+    /// label is the one used, a post can still define a shared label for itself.
+    pub fn with_link_refs(mut self, link_refs: Option<String>) -> Self {
+        self.link_refs = link_refs;
+        self
+    }
+
+    /// Compare the posts written against the `manifest.json` of the previous build,
+    /// reporting (in the build summary and `changes.json`) the posts added, removed,
+    /// retitled or changed, then write a new `manifest.json` for the next build.
+    pub fn with_report_changes(mut self, report_changes: bool) -> Self {
+        self.report_changes = report_changes;
+        self
+    }
+
+    /// Writes the `changes.json` report of how `manifest` differs from the previous build's,
+    /// then replaces the previous manifest with it.
+    fn write_change_report(
+        &self,
+        manifest: &Manifest,
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let manifest_file = format!("{}/manifest.json", &self.rendered_outputs_dir);
+        let previous = Manifest::load(Path::new(&manifest_file)).unwrap_or_else(|e| {
+            warn!(
+                "ignoring the previous manifest, {} is invalid: {}",
+                manifest_file, e
+            );
+            None
+        });
+
+        // without a previous build to compare against, every post is new
+        let changes = manifest.changes_since(&previous.unwrap_or_default());
+
+        let changes_file = format!("{}/changes.json", &self.rendered_outputs_dir);
+        self.write_with_retries(&changes_file, changes.to_json().as_bytes())
+            .map_err(|e| BlogGeneratorError::FileWriteError(changes_file.clone(), e.to_string()))?;
+        self.print_status(None, format!("wrote {:?}", &changes_file));
+
+        self.write_with_retries(&manifest_file, manifest.to_json().as_bytes())
+            .map_err(|e| {
+                BlogGeneratorError::FileWriteError(manifest_file.clone(), e.to_string())
+            })?;
+        self.print_status(None, format!("wrote {:?}", &manifest_file));
+
+        summary.changes = Some(changes);
+
+        Ok(())
+    }
+
+    /// Also print each post to a PDF alongside its html, with `pdf_renderer` (a headless
+    /// Chrome/Chromium or wkhtmltopdf, see `pdf::find_pdf_renderer`).
+    pub fn with_pdf(mut self, pdf_renderer: Option<String>) -> Self {
+        self.pdf_renderer = pdf_renderer;
+        self
+    }
+
+    /// Prints each of the posts written to a PDF next to its html, noting the posts that
+    /// couldn't be printed as problems.
+    fn write_pdfs(&self, renderer: &str, pages: &[Page], summary: &mut BuildSummary) {
+        let progress_bar = self.progress_bar(pages.len());
+        for page in pages {
+            if let Some(progress_bar) = &progress_bar {
+                progress_bar.set_message(page.path.clone());
+                progress_bar.inc(1);
+            }
+
+            let html_path = Path::new(&self.rendered_outputs_dir).join(&page.path);
+            let pdf_path = html_path.with_extension("pdf");
+            let written = html_to_pdf(renderer, &html_path, &pdf_path)
+                .and_then(|_| self.apply_file_mode(&pdf_path).map_err(|e| e.to_string()));
+            match written {
+                Ok(()) => {
+                    self.print_status(
+                        progress_bar.as_ref(),
+                        format!("wrote {:?}", pdf_path.display()),
+                    );
+                    summary.pdfs_written = summary.pdfs_written.map(|count| count + 1);
+                }
+                Err(e) => {
+                    warn!("could not write {}: {}", pdf_path.display(), e);
+                    summary.problems.add(
+                        &format!("{}", html_path.display()),
+                        None,
+                        format!("could not be printed to a PDF: {}", e),
+                    );
+                }
+            }
+        }
+
+        if let Some(progress_bar) = progress_bar {
+            progress_bar.finish_and_clear();
+        }
+    }
+
+    /// Keep the rendered markdown of each post between builds (of this generator, or of
+    /// later runs when there's a `cache_file` to keep it in), so that only the posts which
+    /// have changed get parsed again.
+    pub fn with_cache(mut self, cache: bool, cache_file: Option<PathBuf>) -> Self {
+        if !cache && cache_file.is_none() {
+            return self;
+        }
+
+        let render_cache = match &cache_file {
+            Some(cache_file) => RenderCache::load(cache_file).unwrap_or_else(|e| {
+                warn!(
+                    "starting an empty cache, {} could not be read: {}",
+                    cache_file.display(),
+                    e
+                );
+                RenderCache::default()
+            }),
+            None => RenderCache::default(),
+        };
+        self.render_cache = Some(Arc::new(Mutex::new(render_cache)));
+        self.cache_file = cache_file;
+        self
+    }
+
+    /// Pass the markdown of each post through `transform` before rendering it, for
+    /// preprocessing of your own (macros, includes). It runs once per post, after the
+    /// front matter has been split off (so it sees only the markdown body, and can't
+    /// change the front matter) and before shortcodes are expanded. Line numbers in the
+    /// problem report are those of the source file, so a transform that adds or removes
+    /// lines will throw them off.
+    pub fn with_markdown_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.markdown_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Resolves the `{{ include "..." }}` directives in the posts against the partials in
+    /// `partials_dir`. Without one, the directives are left as they are.
+    pub fn with_partials_dir(mut self, partials_dir: Option<String>) -> Self {
+        self.partials_dir = partials_dir;
+        self
+    }
+
+    /// Reads `key: value` metadata out of the HTML comments at the top of the posts (as
+    /// well as their front matter, which wins where both set a key).
+    pub fn with_comment_metadata(mut self, comment_metadata: bool) -> Self {
+        self.comment_metadata = comment_metadata;
+        self
+    }
+
+    /// The markdown body of a post, with its partials included and after the markdown
+    /// transform (if there is one).
+    fn transform_markdown(
+        &self,
+        source: &str,
+        md_body: &str,
+    ) -> Result<String, BlogGeneratorError> {
+        let md_body = match &self.partials_dir {
+            Some(partials_dir) => expand_includes(md_body, Path::new(partials_dir))
+                .map_err(|e| BlogGeneratorError::IncludeError(source.to_string(), e))?,
+            None => md_body.to_string(),
+        };
+
+        Ok(match &self.markdown_transform {
+            Some(transform) => transform(&md_body),
+            None => md_body,
+        })
+    }
+
+    /// The files a build reads, for watching them for changes.
+    pub fn watched_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = match &self.bundle_file {
+            Some(bundle_file) => vec![PathBuf::from(bundle_file)],
+            None => glob(&format!("{}/{}", &self.markdown_sources_dir, "*.md"))
+                .map(|paths| paths.filter_map(Result::ok).collect())
+                .unwrap_or_default(),
+        };
+        files.extend(
+            [
+                Some(PathBuf::from(&self.css_source_file)),
+                self.favicon.as_ref().map(PathBuf::from),
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        if let Some(partials_dir) = &self.partials_dir {
+            files.extend(
+                glob(&format!("{}/**/*.md", partials_dir))
+                    .map(|paths| paths.filter_map(Result::ok).collect::<Vec<_>>())
+                    .unwrap_or_default(),
+            );
+        }
+
+        files
+    }
+
+    /// Renders the markdown of a post, or takes it from the cache when the post hasn't
+    /// changed since it was last rendered.
+    fn render_markdown(
+        &self,
+        mdf: &MarkDownFile,
+        md_content: &str,
+        summary: &mut BuildSummary,
+    ) -> RenderedMarkdown {
+        let options = self.markdown_options(&mdf.front_matter);
+        let render = || {
+            let body_html =
+                self.markdown_to_html(&strip_excerpt_marker(md_content), &mdf.front_matter);
+            let title_heading = self.offset_heading_level(HeadingLevel::H1).to_string();
+            let excerpt = match markdown_above_marker(md_content) {
+                Some(md_excerpt) => without_title_heading(
+                    &self.markdown_to_html(md_excerpt, &mdf.front_matter),
+                    &title_heading,
+                ),
+                None => first_paragraph(&body_html).unwrap_or_default(),
+            };
+            RenderedMarkdown {
+                title: self.post_title(&mdf.front_matter, &body_html),
+                body_html,
+                excerpt,
+            }
+        };
+
+        let Some(render_cache) = &self.render_cache else {
+            return render();
+        };
+
+        // anything that changes the html produced from the same markdown
+        let settings = format!(
+            "{:?} {} {} {} {} {} {} {} {} {} {} {}",
+            options.bits(),
+            self.heading_offset,
+            self.toc,
+            self.toc_depth,
+            self.number_headings,
+            self.slug_transliterate,
+            self.sanitize,
+            self.disable_raw_html,
+            self.blockquote_cite,
+            self.external_links_new_tab,
+            self.base_url,
+            sha256_hex(self.link_refs.as_deref().unwrap_or_default().as_bytes())
+        );
+        let source = format!("{}", mdf.file_path_buf.display());
+        let modified = fs::metadata(&mdf.file_path_buf)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let key = CacheKey::new(
+            &source,
+            modified,
+            sha256_hex(
+                format!(
+                    "{:?}\n{:?}\n{}\n{:?}\n{}",
+                    mdf.front_matter.meta.get("title"),
+                    mdf.front_matter.toc_exclude,
+                    mdf.front_matter.allow_html,
+                    mdf.front_matter.number_headings,
+                    md_content
+                )
+                .as_bytes(),
+            ),
+            settings,
+        );
+
+        let mut render_cache = render_cache.lock().unwrap();
+        if let Some(rendered) = render_cache.get(&key) {
+            debug!("using the cached rendering of {}", source);
+            summary.cache_hits = summary.cache_hits.map(|count| count + 1);
+            return rendered.clone();
+        }
+
+        summary.cache_misses = summary.cache_misses.map(|count| count + 1);
+        let rendered = render();
+        render_cache.insert(key, rendered.clone());
+
+        rendered
+    }
+
+    fn save_render_cache(&self) {
+        if let (Some(render_cache), Some(cache_file)) = (&self.render_cache, &self.cache_file) {
+            if let Err(e) = render_cache.lock().unwrap().save(cache_file) {
+                warn!(
+                    "could not save the cache to {}: {}",
+                    cache_file.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Writes `<dir>/<slug of name>.html`, listing the given posts (by output path) in order
+    /// under `name` as the heading, using the index template.
+    fn render_listing_page(
+        &self,
+        tera: &SiteTemplates,
+        dir: &str,
+        name: &str,
+        posts: &[String],
+        pages: &[Page],
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let listed_pages: Vec<Page> = posts
+            .iter()
+            .filter_map(|post| pages.iter().find(|page| page.path == *post))
+            .map(|page| Page {
+                url: self.relative_url(1, &page.path),
+                read_more_url: self.relative_url(1, &page.path),
+                ..page.clone()
+            })
+            .collect();
+
+        let mut context = self.index_context(&listed_pages, 1);
+        context.insert("heading", name);
+
+        let rendered = tera.render("index", &context).map_err(|e| {
+            BlogGeneratorError::TemplateUseError("index".to_string(), e.to_string())
+        })?;
+
+        let listing_dir = format!("{}/{}", &self.rendered_outputs_dir, dir);
+        let out_file = format!("{}/{}.html", &listing_dir, self.slug(name));
+        fs::create_dir_all(&listing_dir)
+            .map_err(|e| BlogGeneratorError::FileWriteError(out_file.clone(), e.to_string()))?;
+        self.write_output(&out_file, &rendered, summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        Ok(())
+    }
+
+    /// Writes `archive.html`, listing every post grouped by year and month.
+    fn render_archive_page(
+        &self,
+        tera: &SiteTemplates,
+        markdown_files: &[MarkDownFile],
+        pages: &[Page],
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let dated_pages: Vec<(DateTime<Utc>, &Page)> = markdown_files
+            .iter()
+            .filter_map(|mdf| {
+                let path = mdf.output_path();
+                let page = pages.iter().find(|page| page.path == path)?;
+                Some((mdf.created_time, page))
+            })
+            .collect();
+
+        let mut context = tera::Context::new();
+        context.insert("years", &group_by_month(dated_pages));
+        context.insert("post_count", &pages.len());
+        context.insert("home_url", &self.relative_url(0, ""));
+        self.insert_favicon(&mut context, 0);
+        if let Some(csp_nonce) = &self.csp_nonce {
+            context.insert("csp_nonce", csp_nonce);
+        }
+        if self.includes_on_index {
+            self.insert_includes(&mut context);
+        }
+
+        let rendered = tera.render("archive", &context).map_err(|e| {
+            BlogGeneratorError::TemplateUseError("archive".to_string(), e.to_string())
+        })?;
+
+        let out_file = format!("{}/archive.html", &self.rendered_outputs_dir);
+        self.write_output(&out_file, &rendered, summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        Ok(())
+    }
+
+    /// Copies the favicon into the root of the rendered outputs dir.
+    fn copy_favicon(&self, favicon: &str) -> Result<(), BlogGeneratorError> {
+        let path = Path::new(favicon);
+        if favicon_type(path).is_none() {
+            return Err(BlogGeneratorError::AssetError(
+                favicon.to_string(),
+                "unsupported favicon format, expected a .ico, .png or .svg file".to_string(),
+            ));
+        }
+
+        let file_name = path.file_name().unwrap_or_default();
+        let out_file = Path::new(&self.rendered_outputs_dir).join(file_name);
+        fs::copy(path, &out_file)
+            .and_then(|_| self.apply_file_mode(&out_file))
+            .map_err(|e| BlogGeneratorError::AssetError(favicon.to_string(), e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Writes the RSS (`feed.xml`), Atom (`atom.xml`) and/or JSON (`feed.json`) feeds of the
+    /// posts.
+    fn write_feeds(
+        &self,
+        feed_format: FeedFormat,
+        pages: &[Page],
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        if !self.base_url.contains("://") {
+            warn!(
+                "the base url ({}) isn't absolute, so the links in the feeds won't be either",
+                self.base_url
+            );
+        }
+
+        // everything in a feed comes from the pages already rendered, so that rebuilding
+        // the feeds never goes back to the markdown
+        let mut entries: Vec<FeedEntry> = pages
+            .iter()
+            .map(|page| {
+                let url = self.relative_url(0, &page.path);
+                let (summary, content) = match self.feed_content {
+                    FeedContent::Full => (&page.excerpt, &page.body_html),
+                    FeedContent::Excerpt => (&page.excerpt, &String::new()),
+                    FeedContent::None => (&String::new(), &String::new()),
+                };
+                FeedEntry {
+                    title: page.title.clone(),
+                    summary: absolute_links(summary, &url),
+                    content: absolute_links(content, &url),
+                    url,
+                    published: page.published,
+                    updated: page.updated,
+                }
+            })
+            .collect();
+        sort_entries(&mut entries);
+
+        let mut feeds = Vec::new();
+        if feed_format.rss() {
+            feeds.push((
+                "feed.xml",
+                rss_feed as fn(&FeedChannel, &[FeedEntry]) -> String,
+            ));
+        }
+        if feed_format.atom() {
+            feeds.push(("atom.xml", atom_feed));
+        }
+        if feed_format.json() {
+            feeds.push(("feed.json", json_feed));
+        }
+
+        for (file_name, render_feed) in feeds {
+            let channel = FeedChannel {
+                title: self
+                    .site_title
+                    .clone()
+                    .unwrap_or_else(|| "Blog".to_string()),
+                home_url: self.relative_url(0, ""),
+                feed_url: self.relative_url(0, file_name),
+            };
+            let out_file = format!("{}/{}", &self.rendered_outputs_dir, file_name);
+            self.write_output(&out_file, &render_feed(&channel, &entries), summary)?;
+            self.print_status(None, format!("wrote {:?}", &out_file));
+        }
+
+        Ok(())
+    }
+
+    /// Writes the `recent.html` fragment, listing the newest `recent_count` of the pages.
+    /// Since the fragment is embedded in other sites, the drafts and the posts scheduled
+    /// after `build_time` are left out of it even when they're written.
+    fn write_recent_posts(
+        &self,
+        recent_count: usize,
+        pages: &[Page],
+        build_time: DateTime<Utc>,
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        if !self.base_url.contains("://") {
+            warn!(
+                "the base url ({}) isn't absolute, so the links in recent.html won't be either",
+                self.base_url
+            );
+        }
+
+        let mut recent: Vec<&Page> = pages
+            .iter()
+            .filter(|page| !page.draft && page.published <= build_time)
+            .collect();
+        recent.sort_by(|a, b| b.published.cmp(&a.published).then(a.path.cmp(&b.path)));
+        let posts: Vec<RecentPost> = recent
+            .into_iter()
+            .take(recent_count)
+            .map(|page| RecentPost {
+                title: page.title.clone(),
+                url: self.relative_url(0, &page.path),
+                date: page.date.clone(),
+            })
+            .collect();
+
+        let out_file = format!("{}/recent.html", &self.rendered_outputs_dir);
+        self.write_output(&out_file, &recent_posts_html(&posts), summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        Ok(())
+    }
+
+    /// Writes `blogroll.opml` and the `blogroll.html` page listing the blogs on the blogroll.
+    fn write_blogroll(
+        &self,
+        tera: &SiteTemplates,
+        blogroll: &Blogroll,
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let title = match &self.site_title {
+            Some(site_title) => format!("{} blogroll", site_title),
+            None => "Blogroll".to_string(),
+        };
+        let out_file = format!("{}/blogroll.opml", &self.rendered_outputs_dir);
+        self.write_output(&out_file, &blogroll.to_opml(&title), summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        let blogs: Vec<Page> = blogroll
+            .entries
+            .iter()
+            .map(|entry| Page {
+                title: entry.title.clone(),
+                url: entry
+                    .site_url
+                    .clone()
+                    .unwrap_or_else(|| entry.feed_url.clone()),
+                ..Page::default()
+            })
+            .collect();
+        let mut context = self.index_context(&blogs, 0);
+        context.insert("heading", "Blogroll");
+        let rendered = tera.render("index", &context).map_err(|e| {
+            BlogGeneratorError::TemplateUseError("index".to_string(), e.to_string())
+        })?;
+        let out_file = format!("{}/blogroll.html", &self.rendered_outputs_dir);
+        self.write_output(&out_file, &rendered, summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        Ok(())
+    }
+
+    /// Writes the content hashes collected while writing the pages to `etags.json`.
+    fn write_etags(&self, summary: &BuildSummary) -> Result<(), BlogGeneratorError> {
+        let Some(etags) = &summary.etags else {
+            return Ok(());
+        };
+
+        let out_file = format!("{}/etags.json", &self.rendered_outputs_dir);
+        let json = serde_json::to_string_pretty(etags)
+            .map_err(|e| BlogGeneratorError::FileWriteError(out_file.clone(), e.to_string()))?;
+        self.write_with_retries(&out_file, json.as_bytes())
+            .map_err(|e| BlogGeneratorError::FileWriteError(out_file.clone(), e.to_string()))?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        Ok(())
+    }
+
+    /// Builds the `MarkDownFile` for the markdown source at `path`, splitting off its front
+    /// matter. Returns `None` for posts scheduled after `build_time` or dated before the
+    /// `since` cutoff (noting them in the summary), and fails on invalid front matter in strict mode. `file_name` picks the
+    /// name of the post given its front matter.
+    fn markdown_file<F>(
+        &self,
+        path: &Path,
+        md_content: &str,
+        file_name: F,
+        (mut created_time, mut modified_time): (DateTime<Utc>, DateTime<Utc>),
+        build_time: DateTime<Utc>,
+        summary: &mut BuildSummary,
+    ) -> Result<Option<MarkDownFile>, BlogGeneratorError>
+    where
+        F: FnOnce(&FrontMatter) -> PathBuf,
+    {
+        // split off the front matter, so that only the markdown itself gets rendered
+        let source = format!("{}", path.display());
+        let (front_matter, md_body) =
+            self.split_front_matter(&source, md_content, &mut summary.problems)?;
+        let first_body_line = md_content[..md_content.len() - md_body.len()]
+            .matches('\n')
+            .count()
+            + 1;
+
+        if front_matter.draft && !self.include_drafts {
+            debug!("skipping {}, it is a draft", path.display());
+            summary.drafts_skipped.push(source);
+            return Ok(None);
+        }
+
+        let date = front_matter.date().unwrap_or_else(|e| {
+            warn!("ignoring invalid date in {}: {}", path.display(), e);
+            summary.problems.add(
+                &source,
+                line_of(md_content, "date:"),
+                format!("ignoring invalid date: {}", e),
+            );
+            None
+        });
+        if let Some(date) = date {
+            if date > build_time && !self.publish_future {
+                debug!("skipping {}, scheduled for {}", path.display(), date);
+                summary
+                    .posts_scheduled
+                    .push((format!("{}", path.display()), date.to_rfc3339()));
+                return Ok(None);
+            }
+
+            // an explicit date is the one to list (and sort) the post by
+            created_time = date;
+        }
+
+        if let Some(since) = self.since {
+            let cutoff = since.cutoff(build_time);
+            if created_time < cutoff {
+                debug!("skipping {}, dated before {}", path.display(), cutoff);
+                summary.posts_before_since.push(source);
+                return Ok(None);
+            }
+        }
+
+        let updated = front_matter.updated().unwrap_or_else(|e| {
+            warn!("ignoring invalid updated date in {}: {}", path.display(), e);
+            summary.problems.add(
+                &source,
+                line_of(md_content, "updated:"),
+                format!("ignoring invalid updated date: {}", e),
+            );
+            None
+        });
+        // a post counts as updated when it says so, or going by the commit history, but
+        // not just because its file was touched
+        match updated {
+            Some(updated) => modified_time = updated,
+            None if !self.use_git_dates || date.is_some() => modified_time = created_time,
+            None => {}
+        }
+
+        Ok(Some(MarkDownFile {
+            file_name: file_name(&front_matter),
+            file_path_buf: path.to_path_buf(),
+            output_dir: format!(
+                "{}{}",
+                self.posts_subdir,
+                self.archive_layout
+                    .map(|layout| layout.dir(&created_time))
+                    .unwrap_or_default()
+            ),
+            created_time,
+            modified_time,
+            title_from_md: None,
+            md_content: self.transform_markdown(&source, md_body)?,
+            first_body_line,
+            front_matter,
+        }))
+    }
+
+    /// Reads the posts out of a bundle file, in which they are separated by `---8<---`
+    /// lines. Each post is named by the `slug` (or else the `title`) in its front matter.
+    fn read_bundle(
+        &self,
+        bundle_file: &str,
+        build_time: DateTime<Utc>,
+        summary: &mut BuildSummary,
+    ) -> Result<Vec<MarkDownFile>, BlogGeneratorError> {
+        let metadata = fs::metadata(bundle_file).map_err(|e| {
+            BlogGeneratorError::MarkDownMetadataError(bundle_file.to_string(), e.to_string())
+        })?;
+        if metadata.len() > self.max_file_size {
+            warn!(
+                "skipping {}, its size ({} bytes) is over the limit of {} bytes",
+                bundle_file,
+                metadata.len(),
+                self.max_file_size
+            );
+            summary.oversized_files.push(bundle_file.to_string());
+            return Ok(Vec::new());
+        }
+
+        let bundle = self
+            .read_markdown(Path::new(bundle_file))
+            .map_err(|e| BlogGeneratorError::MarkDownFileError(bundle_file.to_string(), e))?;
+
+        // without front matter dates, every post gets the time of the bundle itself
+        let bundle_time = match self.source_date_epoch {
+            Some(source_date_epoch) => source_date_epoch,
+            None => metadata.modified().map(Into::into).unwrap_or(build_time),
+        };
+
+        let mut markdown_files = Vec::new();
+        for (i, section) in split_bundle(&bundle).into_iter().enumerate() {
+            // the number of the post in the bundle stands in for a file name in messages
+            let path = PathBuf::from(format!("{}#{}", bundle_file, i + 1));
+            let file_name = |front_matter: &FrontMatter| {
+                let slug = front_matter
+                    .meta_str("slug")
+                    .or_else(|| front_matter.meta_str("title"))
+                    .map(|slug| self.slug(slug))
+                    .filter(|slug| !slug.is_empty())
+                    .unwrap_or_else(|| format!("post-{}", i + 1));
+                PathBuf::from(format!("{}.md", slug))
+            };
+
+            if let Some(mdf) = self.markdown_file(
+                &path,
+                section,
+                file_name,
+                (bundle_time, bundle_time),
+                build_time,
+                summary,
+            )? {
+                markdown_files.push(mdf);
+            }
+        }
+
+        Ok(markdown_files)
+    }
+
+    /// Makes sure no two posts are published at the same url (as two posts with the same
+    /// slug, or the same date and slug in an archive layout, would be), which would have
+    /// one silently overwrite the other. The oldest post keeps the url, and the others
+    /// either fail the build or, when disambiguating, get the first free numbered suffix.
+    fn assign_permalinks(
+        &self,
+        markdown_files: &mut [MarkDownFile],
+    ) -> Result<(), BlogGeneratorError> {
+        let mut order: Vec<usize> = (0..markdown_files.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&markdown_files[a], &markdown_files[b]);
+            a.created_time
+                .cmp(&b.created_time)
+                .then_with(|| a.file_path_buf.cmp(&b.file_path_buf))
+        });
+
+        let wanted: BTreeSet<String> = markdown_files.iter().map(|mdf| mdf.output_path()).collect();
+        let mut taken: BTreeMap<String, usize> = BTreeMap::new();
+        for i in order {
+            let output_path = markdown_files[i].output_path();
+            let Some(&first) = taken.get(&output_path) else {
+                taken.insert(output_path, i);
+                continue;
+            };
+
+            let source = format!("{}", markdown_files[i].file_path_buf.display());
+            if !self.disambiguate {
+                return Err(BlogGeneratorError::PermalinkConflict(
+                    format!("{}", markdown_files[first].file_path_buf.display()),
+                    source,
+                    self.relative_url(0, &output_path),
+                ));
+            }
+
+            let mdf = &mut markdown_files[i];
+            let stem = mdf
+                .file_name
+                .to_string_lossy()
+                .trim_end_matches(".md")
+                .to_string();
+            for suffix in 2.. {
+                mdf.file_name = PathBuf::from(format!("{}-{}.md", stem, suffix));
+                let path = mdf.output_path();
+                if !wanted.contains(&path) && !taken.contains_key(&path) {
+                    break;
+                }
+            }
+            warn!(
+                "{} would be published at the url of an older post, so is at {} instead",
+                source,
+                mdf.output_path()
+            );
+            taken.insert(mdf.output_path(), i);
+        }
+
+        Ok(())
+    }
+
+    /// Sources whose names differ only by case (`Post.md` and `post.md`) would overwrite
+    /// each other's output on a case-insensitive filesystem, so warn about them
+    /// (or fail in strict mode).
+    fn check_output_path_collisions(
+        &self,
+        markdown_files: &[MarkDownFile],
+    ) -> Result<(), BlogGeneratorError> {
+        let mut output_paths: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for mdf in markdown_files {
+            output_paths
+                .entry(mdf.output_path().to_lowercase())
+                .or_default()
+                .push(format!("{}", mdf.file_path_buf.display()));
+        }
+
+        for (output_path, sources) in output_paths.iter().filter(|(_, s)| s.len() > 1) {
+            let sources = sources.join(", ");
+            if self.strict {
+                return Err(BlogGeneratorError::OutputPathCollision(
+                    output_path.clone(),
+                    sources,
+                ));
+            }
+            warn!(
+                "the output file {:?} would be written by more than one markdown source file: {}",
+                output_path, sources
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Writes a page at each of the `aliases` of the posts that redirects to the post,
+    /// along with a `_redirects` file listing them for the hosts that read one. An alias
+    /// taken by a post, by another post's alias or by one of the generator's own pages (like
+    /// `index.html` or `tags/rust.html`), or one leading out of the site (`../x.html`), is
+    /// left out with a warning, or fails the build in strict mode.
+    fn write_redirects(
+        &self,
+        markdown_files: &[MarkDownFile],
+        pages: &[Page],
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let post_paths: BTreeSet<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+        let mut aliased: BTreeMap<String, String> = BTreeMap::new();
+        let mut redirects: Vec<(String, String)> = Vec::new();
+
+        for mdf in markdown_files {
+            let post_path = mdf.output_path();
+            if !post_paths.contains(post_path.as_str()) {
+                continue;
+            }
+
+            let source = format!("{}", mdf.file_path_buf.display());
+            for alias in mdf.front_matter.aliases.iter() {
+                let alias = alias.trim_start_matches('/').to_string();
+                let within_site = !alias.is_empty()
+                    && Path::new(&alias)
+                        .components()
+                        .all(|component| matches!(component, Component::Normal(_)));
+                if !within_site {
+                    if self.strict {
+                        return Err(BlogGeneratorError::InvalidAlias(alias, source));
+                    }
+                    warn!(
+                        "the alias {:?} of {} isn't a path within the site",
+                        alias, source
+                    );
+                    summary.problems.add(
+                        &source,
+                        None,
+                        format!("the alias {} isn't a path within the site", alias),
+                    );
+                    continue;
+                }
+
+                let collision = if post_paths.contains(alias.as_str()) {
+                    Some(format!("the post {}", alias))
+                } else if is_generated_path(&alias) {
+                    Some(format!("the generated {}", alias))
+                } else {
+                    aliased
+                        .get(&alias)
+                        .map(|other| format!("an alias of {}", other))
+                };
+                if let Some(collision) = collision {
+                    if self.strict {
+                        return Err(BlogGeneratorError::AliasCollision(alias, collision));
+                    }
+                    warn!("the alias {:?} is already taken by {}", alias, collision);
+                    summary.problems.add(
+                        &source,
+                        None,
+                        format!("the alias {} is already taken by {}", alias, collision),
+                    );
+                    continue;
+                }
+
+                let out_path = format!("{}/{}", &self.rendered_outputs_dir, &alias);
+                if let Some(out_dir) = Path::new(&out_path).parent() {
+                    fs::create_dir_all(out_dir).map_err(|e| {
+                        BlogGeneratorError::FileWriteError(out_path.clone(), e.to_string())
+                    })?;
+                }
+                let depth = alias.matches('/').count();
+                let target_url = self.relative_url(depth, &post_path);
+                self.write_output(&out_path, &redirect_page(&target_url), summary)?;
+                summary.redirects_written += 1;
+
+                redirects.push((
+                    site_path(&self.base_url, &alias),
+                    site_path(&self.base_url, &post_path),
+                ));
+                aliased.insert(alias, post_path.clone());
+            }
+        }
+
+        if redirects.is_empty() {
+            return Ok(());
+        }
+
+        let out_path = format!("{}/_redirects", &self.rendered_outputs_dir);
+        self.write_with_retries(&out_path, redirects_file(&redirects).as_bytes())
+            .map_err(|e| BlogGeneratorError::FileWriteError(out_path, e.to_string()))
+    }
+
+    /// Adds the named template to `tera`, from the custom template file if one is set
+    /// and from the built-in template otherwise.
+    fn add_template(
+        &self,
+        tera: &mut Tera,
+        name: &str,
+        custom_template: &Option<String>,
+        builtin_template: &str,
+    ) -> Result<(), BlogGeneratorError> {
+        let template = match custom_template {
+            Some(path) => fs::read_to_string(path)
+                .map_err(|e| BlogGeneratorError::TemplateAddError(path.clone(), e.to_string()))?,
+            None => builtin_template.to_string(),
+        };
+
+        tera.add_raw_template(name, &template)
+            .map_err(|e| BlogGeneratorError::TemplateAddError(name.to_string(), e.to_string()))
+    }
+
+    fn print_status(&self, progress_bar: Option<&ProgressBar>, message: String) {
+        if self.quiet {
+            return;
+        }
+
+        // printing through the progress bar keeps it from getting torn up by the message
+        match progress_bar {
+            Some(progress_bar) => progress_bar.println(message),
+            None => println!("{}", message),
+        }
+    }
+
+    fn progress_bar(&self, post_count: usize) -> Option<ProgressBar> {
+        if !self.progress || self.quiet || !std::io::stderr().is_terminal() {
+            return None;
+        }
+
+        let progress_bar = ProgressBar::new(post_count as u64);
+        progress_bar.set_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} {wide_msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_bar()),
+        );
+
+        Some(progress_bar)
+    }
+
+    /// Retry failed writes of output files up to `write_retries` times, waiting a little
+    /// longer before each attempt, for output dirs on network filesystems where write
+    /// errors can be transient. At most `MAX_WRITE_RETRIES` retries are made, however many
+    /// are asked for.
+    pub fn with_write_retries(mut self, write_retries: u32) -> Self {
+        self.write_retries = write_retries.min(MAX_WRITE_RETRIES);
+        self
+    }
+
+    /// Give every generated file the Unix permissions `file_mode` (like `0o644`), rather
+    /// than the ones the umask leaves them with. Does nothing on other platforms.
+    pub fn with_file_mode(mut self, file_mode: Option<u32>) -> Self {
+        #[cfg(not(unix))]
+        if file_mode.is_some() {
+            warn!("file permissions can only be set on Unix, ignoring the file mode");
+        }
+        self.file_mode = file_mode;
+        self
+    }
+
+    /// Hold a lock file in the rendered outputs dir while rendering (which is the default),
+    /// so that a second build into the same dir fails rather than clobbering the first.
+    pub fn with_build_lock(mut self, build_lock: bool) -> Self {
+        self.build_lock = build_lock;
+        self
+    }
+
+    /// Gives a generated file the configured permissions, if there are any.
+    fn apply_file_mode(&self, path: &Path) -> std::io::Result<()> {
+        match self.file_mode {
+            Some(file_mode) => set_file_mode(path, file_mode),
+            None => Ok(()),
+        }
+    }
+
+    fn write_with_retries(&self, out_path: &str, bytes: &[u8]) -> std::io::Result<()> {
+        let mut attempt = 0;
+        loop {
+            match fs::write(out_path, bytes) {
+                Ok(()) => return self.apply_file_mode(Path::new(out_path)),
+                Err(e) if attempt < self.write_retries => {
+                    let backoff = write_backoff(attempt);
+                    warn!(
+                        "writing {} failed ({}), retrying in {:?}",
+                        out_path, e, backoff
+                    );
+                    thread::sleep(backoff);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Writes an output file like `write_output`, unless it's already written with the same
+    /// contents, returning whether it was.
+    fn write_output_if_changed(
+        &self,
+        out_path: &str,
+        contents: &str,
+        summary: &mut BuildSummary,
+    ) -> Result<bool, BlogGeneratorError> {
+        let formatted = self.formatted_output(out_path, contents);
+        if fs::read(out_path).is_ok_and(|written| written == formatted.as_bytes()) {
+            return Ok(false);
+        }
+
+        self.write_output(out_path, contents, summary).map(|_| true)
+    }
+
+    /// The contents of an output file as written, with the final formatting applied.
+    fn formatted_output<'a>(&self, out_path: &str, contents: &'a str) -> Cow<'a, str> {
+        let bom = self.bom && !out_path.ends_with(".json");
+        if self.final_newline || self.line_ending.is_some() || bom {
+            Cow::Owned(format_output(
+                contents,
+                self.final_newline,
+                self.line_ending,
+                bom,
+            ))
+        } else {
+            Cow::Borrowed(contents)
+        }
+    }
+
+    /// Writes a generated (text) output file, along with its pre-compressed variants.
+    fn write_output(
+        &self,
+        out_path: &str,
+        contents: &str,
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let write_error = |e: std::io::Error| {
+            BlogGeneratorError::FileWriteError(out_path.to_string(), e.to_string())
+        };
+
+        let contents = self.formatted_output(out_path, contents);
+        let contents = contents.as_ref();
+
+        self.write_with_retries(out_path, contents.as_bytes())
+            .map_err(write_error)?;
+
+        if let Some(etags) = summary.etags.as_mut() {
+            let relative_path = Path::new(out_path)
+                .strip_prefix(&self.rendered_outputs_dir)
+                .unwrap_or(Path::new(out_path));
+            etags.insert(
+                format!("{}", relative_path.display()),
+                sha256_hex(contents.as_bytes()),
+            );
+        }
+
+        if let Some(precompress) = self.precompress {
+            if precompress.gzip() {
+                let compressed = gzip_compress(contents.as_bytes()).map_err(write_error)?;
+                self.write_with_retries(&format!("{}.gz", out_path), &compressed)
+                    .map_err(write_error)?;
+                summary.gzip_bytes =
+                    Some(summary.gzip_bytes.unwrap_or(0) + compressed.len() as u64);
+            }
+            if precompress.brotli() {
+                let compressed = brotli_compress(contents.as_bytes()).map_err(write_error)?;
+                self.write_with_retries(&format!("{}.br", out_path), &compressed)
+                    .map_err(write_error)?;
+                summary.brotli_bytes =
+                    Some(summary.brotli_bytes.unwrap_or(0) + compressed.len() as u64);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the url of the output file at `path` (relative to the rendered outputs dir),
+    /// for a link from a page `depth` directories below the rendered outputs dir.
+    /// A relative base url has to climb back up to the rendered outputs dir first.
+    fn relative_url(&self, depth: usize, path: &str) -> String {
+        if depth > 0 && (self.base_url.is_empty() || self.base_url.starts_with("./")) {
+            return format!("{}{}", "../".repeat(depth), path);
+        }
+
+        format!("{}{}", &self.base_url, path)
+    }
+
+    fn offset_heading_level(&self, level: HeadingLevel) -> HeadingLevel {
+        let shifted = (level as usize + self.heading_offset).min(HeadingLevel::H6 as usize);
+        HeadingLevel::try_from(shifted).unwrap_or(HeadingLevel::H6)
+    }
+
+    fn render_not_found_page(
+        &self,
+        tera: &SiteTemplates,
+        css_from_source: &str,
+        summary: &mut BuildSummary,
+    ) -> Result<(), BlogGeneratorError> {
+        let mut body_content = match &self.not_found_source {
+            Some(source) => {
+                let md_content = self
+                    .read_markdown(Path::new(source))
+                    .map_err(|e| BlogGeneratorError::MarkDownFileError(source.clone(), e))?;
+                self.markdown_to_html(&md_content, &FrontMatter::default())
+            }
+            None => {
+                "<h1>Page not found</h1>\n<p>The page you were looking for does not exist.</p>\n"
+                    .to_string()
+            }
+        };
+        body_content.push_str(&format!(
+            "<p><a href=\"{}index.html\">Back to the index</a></p>\n",
+            &self.base_url
+        ));
+
+        let context = self.page_context(&body_content, css_from_source, 0);
+
+        let rendered = tera
+            .render("html", &context)
+            .map_err(|e| BlogGeneratorError::TemplateUseError("html".to_string(), e.to_string()))?;
+
+        let out_file = format!("{}/404.html", &self.rendered_outputs_dir);
+        self.write_output(&out_file, &rendered, summary)?;
+        self.print_status(None, format!("wrote {:?}", &out_file));
+
+        Ok(())
+    }
+
+    /// Splits the front matter off the markdown source of `source`, falling back to an
+    /// empty front matter (with a warning) when it can't be parsed, or failing in strict
+    /// mode.
+    fn split_front_matter<'a>(
+        &self,
+        source: &str,
+        md_content: &'a str,
+        problems: &mut ProblemReport,
+    ) -> Result<(FrontMatter, &'a str), BlogGeneratorError> {
+        // legacy metadata comments can come before the front matter, or after it
+        let (mut metadata, rest) = match self.comment_metadata {
+            true => split_comment_metadata(md_content),
+            false => (Vec::new(), md_content),
+        };
+        let yaml_start_line = md_content[..md_content.len() - rest.len()]
+            .matches('\n')
+            .count()
+            + 1;
+        let (front_matter_yaml, mut md_body) = split_front_matter(rest);
+        if self.comment_metadata {
+            let (body_metadata, body) = split_comment_metadata(md_body);
+            metadata.extend(body_metadata);
+            md_body = body;
+        }
+        let front_matter_yaml = match (front_matter_yaml, metadata.is_empty()) {
+            (None, false) => Some(""),
+            (front_matter_yaml, _) => front_matter_yaml,
+        };
+
+        let front_matter =
+            match front_matter_yaml.map(|yaml| parse_front_matter_with(yaml, &metadata)) {
+                Some(Ok(front_matter)) => front_matter,
+                Some(Err(e)) => {
+                    // the yaml starts on the line after the opening `---`
+                    let line = e
+                        .location()
+                        .map(|location| location.line() + yaml_start_line);
+                    if self.strict {
+                        let detail = match line {
+                            Some(line) => format!("line {}: {}", line, e),
+                            None => e.to_string(),
+                        };
+                        return Err(BlogGeneratorError::FrontMatterError(
+                            source.to_string(),
+                            detail,
+                        ));
+                    }
+                    warn!("ignoring invalid front matter in {}: {}", source, e);
+                    problems.add(
+                        source,
+                        line,
+                        format!("ignoring invalid front matter: {}", e),
+                    );
+                    FrontMatter::default()
+                }
+                None => FrontMatter::default(),
+            };
+
+        Ok((front_matter, md_body))
+    }
+
+    /// The markdown extensions to render a post with, taking its front matter into account.
+    fn markdown_options(&self, front_matter: &FrontMatter) -> Options {
+        if self.strict_commonmark {
+            return Options::empty();
+        }
+        let mut options = self.markdown_flavor.options() | self.extra_markdown_options;
+        if front_matter.typography == Some(false) {
+            options.remove(Options::ENABLE_SMART_PUNCTUATION);
+        }
+
+        options
+    }
+
+    fn markdown_to_html(&self, md_content: &str, front_matter: &FrontMatter) -> String {
+        let options = self.markdown_options(front_matter);
+        let md_content = match &self.link_refs {
+            Some(link_refs) => format!("{}\n\n{}", md_content, link_refs),
+            None => md_content.to_string(),
+        };
+
+        let disable_raw_html = self.disable_raw_html && !front_matter.allow_html;
+        let parser = Parser::new_ext(&md_content, options)
+            // the html of the shortcodes is left, since they're expanded before parsing
+            .filter(|event| match event {
+                Event::Html(html) => !disable_raw_html || is_shortcode_html(html),
+                _ => true,
+            })
+            .map(|event| match event {
+                Event::Start(Tag::Heading(level, id, classes)) => {
+                    Event::Start(Tag::Heading(self.offset_heading_level(level), id, classes))
+                }
+                Event::End(Tag::Heading(level, id, classes)) => {
+                    Event::End(Tag::Heading(self.offset_heading_level(level), id, classes))
+                }
+                _ => event,
+            });
+
+        let title_level = self.offset_heading_level(HeadingLevel::H1);
+        let numbered = front_matter.number_headings.unwrap_or(self.number_headings);
+        let mut body_content = String::new();
+        if self.toc || numbered {
+            let mut events: Vec<Event> = parser.collect();
+            if numbered {
+                events = number_headings(events, title_level);
+            }
+            if self.toc {
+                let max_depth =
+                    HeadingLevel::try_from(self.toc_depth.clamp(1, 6)).unwrap_or(HeadingLevel::H3);
+                events = add_table_of_contents(
+                    events,
+                    title_level,
+                    max_depth,
+                    &front_matter.toc_exclude,
+                    numbered,
+                    self.slug_transliterate,
+                );
+            }
+            html::push_html(&mut body_content, events.into_iter());
+        } else {
+            html::push_html(&mut body_content, parser);
+        }
+
+        if self.sanitize {
+            body_content = sanitize_html(&body_content);
+        }
+
+        if options.contains(Options::ENABLE_TABLES) {
+            body_content = add_alignment_classes(&body_content);
+        }
+
+        if self.blockquote_cite {
+            body_content = add_blockquote_citations(&body_content);
+        }
+
+        if self.external_links_new_tab {
+            body_content = open_external_links_in_new_tab(&body_content, &self.base_url);
+        }
+
+        body_content
+    }
+
+    /// The template context shared by every page rendered with the html template, for a
+    /// page `depth` folders deep in the rendered outputs dir.
+    fn page_context(
+        &self,
+        body_content: &str,
+        css_from_source: &str,
+        depth: usize,
+    ) -> tera::Context {
+        let mut context = tera::Context::new();
+        match self
+            .code_copy_button
+            .then(|| add_copy_buttons(body_content))
+            .flatten()
+        {
+            Some(body_content) => {
+                context.insert("body_content", &body_content);
+                context.insert("code_copy", &true);
+            }
+            None => context.insert("body_content", body_content),
+        }
+        context.insert("css_from_source", css_from_source);
+        context.insert("home_url", &self.relative_url(depth, ""));
+        self.insert_favicon(&mut context, depth);
+        self.insert_layout(&mut context);
+        self.insert_includes(&mut context);
+        if self.critical_css.is_some() && !self.rendered_outputs_dir.is_empty() {
+            context.insert("stylesheet_url", &self.relative_url(depth, "site.css"));
+        }
+        if self.site_header {
+            context.insert("site_header", &true);
+            context.insert("site_title", self.site_title.as_deref().unwrap_or("Home"));
+        }
+        if let Some(content_class) = &self.content_class {
+            context.insert("content_class", content_class);
+        }
+        if let Some(print_css) = &self.print_css {
+            context.insert("print_css", print_css);
+        }
+        if let Some(csp_nonce) = &self.csp_nonce {
+            context.insert("csp_nonce", csp_nonce);
+        }
+        // the scrolling only matters when there are table of contents links to follow
+        if self.toc && self.smooth_scroll {
+            context.insert("smooth_scroll", &true);
+            context.insert("scroll_offset", &self.scroll_offset);
+        }
+
+        context
+    }
+
+    fn insert_favicon(&self, context: &mut tera::Context, depth: usize) {
+        let Some(favicon) = &self.favicon else {
+            return;
+        };
+        let Some(file_name) = Path::new(favicon).file_name() else {
+            return;
+        };
+
+        let favicon_url = self.relative_url(depth, &file_name.to_string_lossy());
+        context.insert("favicon_url", &favicon_url);
+        context.insert(
+            "favicon_type",
+            favicon_type(Path::new(favicon)).unwrap_or_default(),
+        );
+        // only png icons work as touch icons
+        if favicon_type(Path::new(favicon)) == Some("image/png") {
+            context.insert("touch_icon_url", &favicon_url);
+        }
+    }
+
+    /// Adds the content width and base font size, as css values.
+    fn insert_layout(&self, context: &mut tera::Context) {
+        if let Some(content_width) = self.content_width {
+            context.insert("content_width", &content_width);
+        }
+        if let Some(font_scale) = self.font_scale {
+            // as a percentage of the browser's default, which the reader may have changed
+            let font_size = format!("{}%", (font_scale * 1000.0).round() / 10.0);
+            context.insert("font_size", &font_size);
+        }
+    }
+
+    /// Adds the snippets to include verbatim in the `<head>` and at the end of the `<body>`.
+    fn insert_includes(&self, context: &mut tera::Context) {
+        if let Some(head_extra) = &self.head_include {
+            context.insert("head_extra", head_extra);
+        }
+        if let Some(body_end_extra) = &self.body_end_include {
+            context.insert("body_end_extra", body_end_extra);
+        }
+    }
+
+    /// The template context of an index page `depth` folders deep in the rendered outputs dir.
+    fn index_context(&self, pages: &[Page], depth: usize) -> tera::Context {
+        let mut context = tera::Context::new();
+        match self.index_title_max_chars {
+            Some(max_chars) => {
+                let pages: Vec<Page> = pages
+                    .iter()
+                    .map(|page| Page {
+                        short_title: truncate_title(&page.title, max_chars),
+                        ..page.clone()
+                    })
+                    .collect();
+                context.insert("pages", &pages);
+            }
+            None => context.insert("pages", pages),
+        }
+        context.insert("show_excerpts", &self.index_excerpts);
+        self.insert_favicon(&mut context, depth);
+        self.insert_layout(&mut context);
+        if let Some(csp_nonce) = &self.csp_nonce {
+            context.insert("csp_nonce", csp_nonce);
+        }
+        if self.includes_on_index {
+            self.insert_includes(&mut context);
+        }
+
+        context
+    }
+
+    /// Reads a markdown source file, in the source encoding if it isn't UTF-8.
+    fn read_markdown(&self, path: &Path) -> Result<String, String> {
+        read_source(path, self.source_encoding).map(normalize_line_endings)
+    }
+
+    fn load_css(&self) -> Result<String, BlogGeneratorError> {
+        if self.resolve_css_imports {
+            return resolve_css_imports(Path::new(&self.css_source_file));
+        }
+
+        let mut css_from_source = String::new();
+        let mut css_f = File::open(&self.css_source_file).map_err(|e| {
+            BlogGeneratorError::InvalidCSSPath(self.css_source_file.clone(), e.to_string())
+        })?;
+        css_f.read_to_string(&mut css_from_source).map_err(|e| {
+            BlogGeneratorError::CSSSourceError(self.css_source_file.clone(), e.to_string())
+        })?;
+
+        Ok(css_from_source)
+    }
+
+    /// The site-wide variables for the templates of a build at `build_time`.
+    fn site(&self, build_time: DateTime<Utc>, post_count: usize) -> Site {
+        Site {
+            title: self.site_title.clone(),
+            base_url: self.base_url.clone(),
+            build_time: build_time.to_rfc3339(),
+            post_count,
+        }
+    }
+
+    /// Renders a single markdown document into a complete html page, styled with the
+    /// configured CSS (or into just the html fragment, in bare mode).
+    pub fn render_str(&self, md_content: &str) -> Result<String, BlogGeneratorError> {
+        let md_content = normalize_line_endings(md_content.to_string());
+        let (front_matter, md_content) =
+            self.split_front_matter("<stdin>", &md_content, &mut ProblemReport::default())?;
+        let md_content = self.transform_markdown("<stdin>", md_content)?;
+        let body_content = self.markdown_to_html(&strip_excerpt_marker(&md_content), &front_matter);
+        // the css is checked even when it isn't used, as it is for a whole site
+        let css_from_source = self.load_css()?;
+        if self.bare {
+            return Ok(body_content);
+        }
+
+        let mut templates = Tera::default();
+        self.add_template(
+            &mut templates,
+            "html",
+            &self.post_template,
+            get_html_template(),
+        )?;
+        let tera = SiteTemplates {
+            templates,
+            site: self.site(self.source_date_epoch.unwrap_or_else(Utc::now), 1),
+        };
+
+        let mut context = self.page_context(&body_content, &css_from_source, 0);
+        context.insert("meta", &front_matter.meta);
+        if let Some(lang) = &front_matter.lang {
+            context.insert("lang", lang);
+        }
+
+        tera.render("html", &context)
+            .map_err(|e| BlogGeneratorError::TemplateUseError("html".to_string(), e.to_string()))
+    }
+
+    /// Renders the whole site once for each of the named css sources (light and dark
+    /// variants, say), each into a subfolder of the rendered outputs dir named after it (and
+    /// so under that subfolder of the base url).
+    pub fn render_css_variants(
+        &self,
+        variants: &[(String, String)],
+    ) -> Result<Vec<(String, BuildSummary)>, BlogGeneratorError> {
+        let mut summaries = Vec::new();
+        for (name, css_source_file) in variants {
+            validate_css_source(css_source_file)?;
+
+            let out_dir = format!("{}/{}", &self.rendered_outputs_dir, name);
+            fs::create_dir_all(&out_dir).map_err(|e| {
+                BlogGeneratorError::InvalidRenderedOutputPath(out_dir.clone(), e.to_string())
+            })?;
+
+            let mut variant = self.clone();
+            variant.css_source_file = css_source_file.clone();
+            variant.rendered_outputs_dir = out_dir;
+            variant.base_url = format!("{}{}/", &self.base_url, name);
+            summaries.push((name.clone(), variant.render()?));
+        }
+
+        Ok(summaries)
+    }
+
+    /// The title of a post: the front matter `title`, or else the text of its (offset)
+    /// title heading.
+    fn post_title(&self, front_matter: &FrontMatter, body_html: &str) -> Option<String> {
+        if let Some(title) = front_matter.meta_str("title") {
+            return Some(title.to_string());
+        }
+
+        let title_heading = self.offset_heading_level(HeadingLevel::H1);
+        let selector = Selector::parse(&title_heading.to_string()).ok()?;
+        Html::parse_fragment(body_html)
+            .select(&selector)
+            .next()
+            .and_then(|h1| h1.text().next())
+            .map(str::to_string)
+    }
+
+    /// Lists the posts a build would publish, in the order they would be listed, without
+    /// writing anything.
+    pub fn list_posts(&self) -> Result<PostList, BlogGeneratorError> {
+        let build_time = self.source_date_epoch.unwrap_or_else(Utc::now);
+        let mut summary = BuildSummary::default();
+
+        let posts = self
+            .discover_posts(build_time, &mut summary)?
+            .into_iter()
+            .filter_map(|mdf| {
+                let body_content = self
+                    .markdown_to_html(&strip_excerpt_marker(&mdf.md_content), &mdf.front_matter);
+                // the posts without a title are skipped by the build, so aren't listed
+                let title = self.post_title(&mdf.front_matter, &body_content)?;
+                Some(PostListing {
+                    source: format!("{}", mdf.file_path_buf.display()),
+                    title,
+                    date: mdf.created_time.format(&self.date_format).to_string(),
+                    tags: mdf.front_matter.tags.clone(),
+                    draft: mdf.front_matter.draft,
+                })
+            })
+            .collect();
+
+        Ok(PostList { posts })
+    }
+
+    /// Finds the markdown sources (or the posts of the bundle) that would be published,
+    /// sorted by date, leaving out the drafts, the scheduled and the oversized ones.
+    fn discover_posts(
+        &self,
+        build_time: DateTime<Utc>,
+        summary: &mut BuildSummary,
+    ) -> Result<Vec<MarkDownFile>, BlogGeneratorError> {
+        let mut markdown_files: Vec<MarkDownFile> = Vec::new();
+        let md_glob_path = format!("{}/{}", &self.markdown_sources_dir, "*.md");
+
+        // a bundle stands in for the markdown files in the sources dir
+        let md_entries = match &self.bundle_file {
+            Some(bundle_file) => {
+                markdown_files = self.read_bundle(bundle_file, build_time, summary)?;
+                Vec::new()
+            }
+            None => glob(&md_glob_path)
+                .expect("failed to read any markdown source files")
+                .collect(),
+        };
+
+        // for each markdown file, append to a vec of MarkDownFile type
+        // later sort by creation date
+        for entry in md_entries {
+            match entry {
+                Ok(path) => {
+                    //let file_path = format!("{:?}", &path.display());
+                    //println!("{:?}", &file_path);
+
+                    let f = File::open(&path).map_err(|e| {
+                        BlogGeneratorError::MarkDownFileError(
+                            format!("{}", &path.display()),
+                            e.to_string(),
+                        )
+                    });
+
+                    let f_metadata = f.unwrap().metadata().map_err(|e| {
+                        BlogGeneratorError::MarkDownMetadataError(
+                            format!("{}", &path.display()),
+                            e.to_string(),
+                        )
+                    });
+
+                    let f_metadata = f_metadata.unwrap();
+
+                    // rather than risk running out of memory reading something that isn't a post
+                    if f_metadata.len() > self.max_file_size {
+                        warn!(
+                            "skipping {}, its size ({} bytes) is over the limit of {} bytes",
+                            path.display(),
+                            f_metadata.len(),
+                            self.max_file_size
+                        );
+                        summary.oversized_files.push(format!("{}", path.display()));
+                        continue;
+                    }
+
+                    let (mut created_time, mut modified_time) =
+                        file_times(f_metadata.created(), f_metadata.modified(), build_time);
+
+                    // filesystem times vary between checkouts, so use the fixed time instead
+                    if let Some(source_date_epoch) = self.source_date_epoch {
+                        created_time = source_date_epoch;
+                        modified_time = source_date_epoch;
+                    }
+
+                    // filesystem times get reset by a checkout, so prefer the commit history
+                    if self.use_git_dates {
+                        match git_dates(&path) {
+                            Some(dates) => {
+                                created_time = dates.created;
+                                modified_time = dates.modified;
+                            }
+                            None => debug!(
+                                "no git history for {}, using filesystem times",
+                                path.display()
+                            ),
+                        }
+                    }
+
+                    let md_content = match self.read_markdown(&path) {
+                        Ok(md_content) => md_content,
+                        Err(e) => {
+                            warn!("could not read {}: {}", path.display(), e);
+                            summary.problems.add(
+                                &format!("{}", path.display()),
+                                None,
+                                format!("could not be read: {}", e),
+                            );
+                            continue;
+                        }
+                    };
+
+                    // there's nothing to render (not even a title) in an empty file
+                    if md_content.trim().is_empty() {
+                        if self.strict {
+                            return Err(BlogGeneratorError::EmptyMarkDownFile(format!(
+                                "{}",
+                                path.display()
+                            )));
+                        }
+                        warn!("skipping {}, it is empty", path.display());
+                        summary.empty_files.push(format!("{}", path.display()));
+                        continue;
+                    }
+
+                    let file_name = PathBuf::from(&path.file_name().unwrap());
+                    let Some(mdf) = self.markdown_file(
+                        &path,
+                        &md_content,
+                        |_| file_name,
+                        (created_time, modified_time),
+                        build_time,
+                        summary,
+                    )?
+                    else {
+                        continue;
+                    };
+
+                    markdown_files.push(mdf);
+                }
+                Err(e) => {
+                    println!("{:?}", e);
+                    continue;
+                }
+            }
+        }
+
+        self.assign_permalinks(&mut markdown_files)?;
+        self.check_output_path_collisions(&markdown_files)?;
+
+        // sort the vector of markdown files by created date (which is the front matter
+        // date, when there is one). Files checked out together share a timestamp, so
+        // ties are broken by file name and then path, keeping the order stable. When
+        // ordering by `order`, the date only orders the posts with the same (or no) order.
+        markdown_files.sort_by(|a, b| {
+            let by_order = match self.post_order {
+                PostOrder::Date => Ordering::Equal,
+                PostOrder::Order => match (a.front_matter.order, b.front_matter.order) {
+                    (Some(a_order), Some(b_order)) => a_order.cmp(&b_order),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+            };
+            by_order
+                .then_with(|| a.created_time.cmp(&b.created_time))
+                .then_with(|| a.file_name.cmp(&b.file_name))
+                .then_with(|| a.file_path_buf.cmp(&b.file_path_buf))
+        });
+
+        Ok(markdown_files)
+    }
+
+    pub fn render(&self) -> Result<BuildSummary, BlogGeneratorError> {
+        self.render_posts(None)
+    }
+
+    /// Rebuilds the site after the markdown sources in `changed` have changed (or been
+    /// added or removed). Every post's page is rendered again, since each depends on the
+    /// rest of the posts (through the series, languages and post count), but only those
+    /// that come out differently from the ones already written are written again; the Open
+    /// Graph images of the posts that haven't changed are kept as they are. Everything
+    /// depending on the whole set of posts (the index, the feeds, the tag pages and so on)
+    /// is written again as well. When anything other than a post has changed, like the css
+    /// or a partial, the whole site is rebuilt instead.
+    pub fn render_changed(&self, changed: &[PathBuf]) -> Result<BuildSummary, BlogGeneratorError> {
+        let posts_only = self.bundle_file.is_none()
+            && changed.iter().all(|path| {
+                path.extension().is_some_and(|extension| extension == "md")
+                    && path.parent() == Some(Path::new(&self.markdown_sources_dir))
+            });
+        if !posts_only {
+            return self.render_posts(None);
+        }
+
+        self.render_posts(Some(changed))
+    }
+
+    /// Renders the site, leaving the pages of the posts that haven't changed as they are
+    /// when only the `changed` sources have.
+    fn render_posts(
+        &self,
+        changed: Option<&[PathBuf]>,
+    ) -> Result<BuildSummary, BlogGeneratorError> {
+        // two builds writing into the same dir at once would leave a mix of both behind
+        let build_lock = if self.build_lock {
+            let build_lock =
+                BuildLock::acquire(Path::new(&self.rendered_outputs_dir)).map_err(|e| {
+                    BlogGeneratorError::BuildLocked(
+                        format!("{}/{}", &self.rendered_outputs_dir, BUILD_LOCK_FILE),
+                        e,
+                    )
+                })?;
+            Some(build_lock)
+        } else {
+            None
+        };
+
+        let mut summary = BuildSummary::default();
+        if self.check_alt {
+            summary.images_missing_alt = Some(0);
+        }
+        if self.copy_images {
+            summary.images_copied = Some(0);
+        }
+        if self.optimize_images {
+            summary.webp_images_generated = Some(0);
+        }
+        if self.etags {
+            // the pages left as they are keep the etags they were written with
+            let etags = match changed {
+                Some(_) => fs::read_to_string(format!("{}/etags.json", &self.rendered_outputs_dir))
+                    .ok()
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+                None => BTreeMap::new(),
+            };
+            summary.etags = Some(etags);
+        }
+        if self.pdf_renderer.is_some() {
+            summary.pdfs_written = Some(0);
+        }
+        if self.og_images {
+            summary.og_images_generated = Some(0);
+        }
+        if self.render_cache.is_some() {
+            summary.cache_hits = Some(0);
+            summary.cache_misses = Some(0);
+        }
+
+        let mut css_from_source = self.load_css()?;
+
+        // the rest of the css is loaded from its own file, after the page has rendered
+        if let Some(critical_css) = &self.critical_css {
+            let out_file = format!("{}/site.css", &self.rendered_outputs_dir);
+            self.write_with_retries(&out_file, css_from_source.as_bytes())
+                .map_err(|e| BlogGeneratorError::FileWriteError(out_file.clone(), e.to_string()))?;
+            self.print_status(None, format!("wrote {:?}", &out_file));
+            css_from_source = critical_css.clone();
+        }
+
+        if self.canonical_urls && !self.base_url.contains("://") {
+            warn!(
+                "the base url ({}) isn't absolute, so the canonical urls won't be either",
+                self.base_url
+            );
+        }
+
+        let og_background = self
+            .og_background
+            .as_ref()
+            .map(|background| {
+                image::open(background).map_err(|e| {
+                    BlogGeneratorError::AssetError(
+                        format!("{}", background.display()),
+                        e.to_string(),
+                    )
+                })
+            })
+            .transpose()?;
+        let og_font = match self.og_images {
+            true => {
+                Some(load_font(self.og_font.as_deref()).map_err(BlogGeneratorError::OgFontError)?)
+            }
+            false => None,
+        };
+
+        // posts dated after this are scheduled, and left out until a later build
+        let build_time = self.source_date_epoch.unwrap_or_else(Utc::now);
+
+        if let Some(static_dir) = &self.theme_static_dir {
+            let copied = copy_dir_recursive(
+                static_dir,
+                Path::new(&self.rendered_outputs_dir),
+                self.file_mode,
+            )
+            .map_err(|e| {
+                BlogGeneratorError::AssetError(format!("{}", static_dir.display()), e.to_string())
+            })?;
+            debug!("copied {} theme static files", copied);
+        }
+
+        if let Some(favicon) = &self.favicon {
+            self.copy_favicon(favicon)?;
+        }
+
+        let mut markdown_files_sorted = self.discover_posts(build_time, &mut summary)?;
+
+        let mut templates = Tera::default();
+        self.add_template(
+            &mut templates,
+            "html",
+            &self.post_template,
+            get_html_template(),
+        )?;
+        for rule in self.template_rules.iter() {
+            let name = rule_template_name(&rule.template);
+            if !templates.get_template_names().any(|added| added == name) {
+                self.add_template(&mut templates, &name, &Some(rule.template.clone()), "")?;
+            }
+        }
+
+        // the markdown is rendered up front, so the post pages can tell how many of the posts
+        // get a page (those without a title are skipped)
+        let rendered_posts: Vec<(String, RenderedMarkdown)> = markdown_files_sorted
+            .iter()
+            .map(|mdf| {
+                if let Some(build_lock) = &build_lock {
+                    build_lock.refresh();
+                }
+                let md_content = self.expand_shortcodes(mdf, &mut summary);
+                let rendered = self.render_markdown(mdf, &md_content, &mut summary);
+                (md_content, rendered)
+            })
+            .collect();
+        let post_count = rendered_posts
+            .iter()
+            .filter(|(_, rendered)| rendered.title.is_some())
+            .count();
+        let mut tera = SiteTemplates {
+            templates,
+            site: self.site(build_time, post_count),
+        };
+
+        let mut pages: Vec<Page> = Vec::new();
+        let mut json_posts: Vec<JsonPostSummary> = Vec::new();
+
+        // the posts written, for comparing against the previous build
+        let mut manifest = Manifest::default();
+
+        // the pages of the posts written by this build
+        let mut written_pages: Vec<String> = Vec::new();
+
+        // keep track of which source files produce each title, so duplicates can be reported
+        let mut titles: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        // local images already copied to the output dir, and whether they have a webp version
+        let mut copied_images: HashMap<String, bool> = HashMap::new();
+
+        // group the posts of each series (in date order), for linking the parts together
+        let mut series_parts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for mdf in markdown_files_sorted.iter() {
+            if let Some(series) = &mdf.front_matter.series {
+                series_parts
+                    .entry(series.clone())
+                    .or_default()
+                    .push(mdf.output_path());
+            }
+        }
+
+        // likewise the posts carrying each tag, under the first spelling of the tags with
+        // the same slug (`Rust` and `rust`), since those share a page
+        let mut tag_posts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut tag_slugs: HashMap<String, String> = HashMap::new();
+        for mdf in markdown_files_sorted.iter() {
+            for name in mdf.front_matter.tags.iter() {
+                let tag = tag_slugs
+                    .entry(self.slug(name))
+                    .or_insert_with(|| name.clone())
+                    .clone();
+                if tag != *name {
+                    warn!(
+                        "{} is tagged {:?}, listed along with {:?} (they have the same slug)",
+                        mdf.file_path_buf.display(),
+                        name,
+                        tag
+                    );
+                }
+                let posts = tag_posts.entry(tag).or_default();
+                if !posts.contains(&mdf.output_path()) {
+                    posts.push(mdf.output_path());
+                }
+            }
+        }
+
+        // and the posts written by each author (filled in as they're written), with the
+        // author going by each slug, since those share a page
+        let mut author_posts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut author_slugs: HashMap<String, String> = HashMap::new();
+
+        // and the posts in each language, which get their own index page
+        let mut lang_posts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for mdf in markdown_files_sorted.iter() {
+            if let Some(lang) = &mdf.front_matter.lang {
+                lang_posts
+                    .entry(lang.clone())
+                    .or_default()
+                    .push(mdf.output_path());
+            }
+        }
+
+        // the progress bar is thread safe, so it can be shared should the rendering ever be
+        // split over several threads
+        //
+        // the posts are rendered one after the other, with no more than one of their files
+        // open at a time, so a low limit on open files can't be run into here; bounding
+        // the files open at once only becomes necessary once the rendering is split
+        let progress_bar = self.progress_bar(markdown_files_sorted.len());
+
+        for (mdf, (md_content, rendered)) in markdown_files_sorted.iter_mut().zip(rendered_posts) {
+            if let Some(build_lock) = &build_lock {
+                build_lock.refresh();
+            }
+            if let Some(progress_bar) = &progress_bar {
+                progress_bar.set_message(format!("{}", mdf.file_name.display()));
+                progress_bar.inc(1);
+            }
+
+            // the Open Graph image of a post that hasn't changed is already written
+            let unchanged = changed.is_some_and(|changed| !changed.contains(&mdf.file_path_buf));
+
+            let mut body_content = rendered.body_html;
+            if self.figures {
+                body_content = self.number_figures(mdf, &md_content, &body_content, &mut summary);
+            }
+            let excerpt = rendered.excerpt;
+            let truncated = is_truncated(
+                &excerpt,
+                &body_content,
+                &self.offset_heading_level(HeadingLevel::H1).to_string(),
+            );
+
+            // also try and scrape out the title from the markdown file
+            let raw_title = match rendered.title {
+                Some(title) => title,
+                None => {
+                    // the title heading is shifted along with all the others
+                    let title_heading = self.offset_heading_level(HeadingLevel::H1);
+                    let source = format!("{}", mdf.file_path_buf.display());
+                    warn!("skipping {}, it has no title heading", source);
+                    summary.problems.add(
+                        &source,
+                        None,
+                        format!("skipped, no title heading ({}) found", title_heading),
+                    );
+                    continue;
+                }
+            };
+            let title_text = format!("{:?}", raw_title);
+            self.print_status(
+                progress_bar.as_ref(),
+                format!("Entry title: {:?}", &title_text),
+            );
+            mdf.title_from_md = Some(title_text.clone());
+
+            titles
+                .entry(title_text.replace('"', ""))
+                .or_default()
+                .push(format!("{}", mdf.file_path_buf.display()));
+
+            if self.check_alt {
+                let source = format!("{}", mdf.file_path_buf.display());
+                let report = find_images_missing_alt(&body_content);
+                for src in report.missing.iter() {
+                    if self.strict {
+                        return Err(BlogGeneratorError::MissingAltText(source, src.clone()));
+                    }
+                    warn!("{} has an image without alt text: {}", source, src);
+                    summary.problems.add(
+                        &source,
+                        line_of(&md_content, src).map(|line| line + mdf.first_body_line - 1),
+                        format!("image without alt text: {}", src),
+                    );
+                }
+                if report.decorative > 0 {
+                    debug!("{} has {} decorative image(s)", source, report.decorative);
+                }
+                summary.images_missing_alt = summary
+                    .images_missing_alt
+                    .map(|count| count + report.missing.len());
+            }
+
+            if self.check_headings {
+                self.check_headings(mdf, &md_content, &body_content, &mut summary)?;
+            }
+            if self.strict_commonmark {
+                self.check_commonmark(mdf, &mdf.md_content, &mut summary);
+            }
+
+            // posts in a subfolder link back up to the root for everything else
+            let depth = mdf.output_depth();
+
+            if let Some(max_bytes) = self.inline_images_max_bytes {
+                body_content =
+                    inline_small_images(&body_content, &self.markdown_sources_dir, max_bytes);
+            }
+            if self.copy_images {
+                body_content =
+                    self.copy_local_images(&body_content, &mut copied_images, &mut summary);
+                if depth > 0 {
+                    body_content = prefix_local_image_sources(&body_content, &"../".repeat(depth));
+                }
+            }
+
+            // a video or audio file is of no use left behind, so those are always copied
+            let source = format!("{}", mdf.file_path_buf.display());
+            let mut hero_video = mdf
+                .front_matter
+                .hero_video
+                .as_deref()
+                .map(|src| video_html(src, mdf.front_matter.hero_poster.as_deref()));
+            for html in std::iter::once(&mut body_content).chain(hero_video.as_mut()) {
+                self.copy_local_media(html, &source, &mut copied_images, &mut summary);
+                if depth > 0 {
+                    *html = prefix_local_media_sources(html, &"../".repeat(depth));
+                }
+            }
+            if self.relative_links {
+                body_content = relative_links(&body_content, &self.base_url, &mdf.output_path());
+            }
+
+            let word_count = word_count(&body_content);
+            let long_read = word_count > self.long_read_threshold;
+
+            // render the template
+            let mut context = self.page_context(&body_content, &css_from_source, depth);
+            context.insert("word_count", &word_count);
+            context.insert("long_read", &long_read);
+            context.insert("meta", &mdf.front_matter.meta);
+            if self.data_attributes {
+                context.insert(
+                    "data_attributes",
+                    &self.data_attributes(mdf, raw_title.trim()),
+                );
+            }
+            let canonical_url = match &mdf.front_matter.canonical {
+                Some(canonical) => Some(canonical.clone()),
+                None if self.canonical_urls => {
+                    Some(format!("{}{}", &self.base_url, mdf.output_path()))
+                }
+                None => None,
+            };
+            if let Some(canonical_url) = canonical_url {
+                context.insert(
+                    "canonical_url",
+                    &html_escape::encode_double_quoted_attribute(&canonical_url),
+                );
+            }
+            if let Some(hero_video) = &hero_video {
+                context.insert("hero_video", hero_video);
+            }
+            if let Some(og_font) = &og_font {
+                let og_image_url = match mdf.front_matter.meta_str("image") {
+                    Some(image) if image.contains("://") => Some(image.to_string()),
+                    Some(image) => Some(format!(
+                        "{}{}",
+                        &self.base_url,
+                        image.trim_start_matches('/')
+                    )),
+                    None => self.write_og_image(
+                        mdf,
+                        raw_title.trim(),
+                        og_background.as_ref(),
+                        og_font,
+                        unchanged,
+                        &mut summary,
+                    ),
+                };
+                if let Some(og_image_url) = og_image_url {
+                    context.insert(
+                        "og_image_url",
+                        &html_escape::encode_double_quoted_attribute(&og_image_url),
+                    );
+                }
+            }
+            let published = mdf.created_time.format(&self.date_format).to_string();
+            let updated = mdf.modified_time.format(&self.date_format).to_string();
+            if self.use_git_dates {
+                context.insert("last_updated", &updated);
+            }
+            context.insert("published", &published);
+            context.insert("updated", &updated);
+            if let Some(lang) = &mdf.front_matter.lang {
+                context.insert("lang", lang);
+                let languages: Vec<LanguageIndex> = lang_posts
+                    .keys()
+                    .map(|lang| LanguageIndex {
+                        lang: lang.clone(),
+                        url: self.relative_url(depth, &language_index_path(lang)),
+                    })
+                    .collect();
+                context.insert("languages", &languages);
+            }
+            if let Some(series) = &mdf.front_matter.series {
+                let parts = &series_parts[series];
+                let part_index = parts.iter().position(|p| *p == mdf.output_path()).unwrap();
+                context.insert("series", series);
+                context.insert("series_part", &(part_index + 1));
+                context.insert("series_parts", &parts.len());
+                context.insert(
+                    "series_url",
+                    &self.relative_url(depth, &format!("series/{}.html", self.slug(series))),
+                );
+                if part_index > 0 {
+                    context.insert(
+                        "series_prev_url",
+                        &self.relative_url(depth, &parts[part_index - 1]),
+                    );
+                }
+                if let Some(next) = parts.get(part_index + 1) {
+                    context.insert("series_next_url", &self.relative_url(depth, next));
+                }
+            }
+
+            let template = template_for(&self.template_rules, &mdf.file_name.to_string_lossy())
+                .map_or("html".to_string(), rule_template_name);
+            let rendered = if self.bare {
+                Ok(body_content.clone())
+            } else {
+                tera.render(&template, &context)
+            };
+            if let Ok(rendered) = rendered {
+                //println!("{:?}", &rendered);
+                let title = &*mdf.title_from_md.clone().unwrap();
+                let out_file_name = mdf.output_path();
+
+                let out_path = format!("{}/{}", &self.rendered_outputs_dir, &out_file_name);
+                if depth > 0 {
+                    let out_dir = format!("{}/{}", &self.rendered_outputs_dir, &mdf.output_dir);
+                    fs::create_dir_all(&out_dir).map_err(|e| {
+                        BlogGeneratorError::FileWriteError(out_path.clone(), e.to_string())
+                    })?;
+                }
+
+                // when rebuilding after a change, the pages that come out the same are left
+                let written = if changed.is_some() {
+                    self.write_output_if_changed(&out_path, &rendered, &mut summary)
+                } else {
+                    self.write_output(&out_path, &rendered, &mut summary)
+                        .map(|_| true)
+                };
+                match written {
+                    Ok(written) => {
+                        if written {
+                            self.print_status(
+                                progress_bar.as_ref(),
+                                format!("wrote {:?}", &out_path),
+                            );
+                            summary.posts_written += 1;
+                            written_pages.push(out_file_name.clone());
+                        }
+                        let page = Page {
+                            title: title.to_string().replace("\"", ""),
+                            url: self.relative_url(0, &out_file_name),
+                            excerpt: excerpt.clone(),
+                            truncated,
+                            read_more_url: self.relative_url(0, &out_file_name),
+                            long_read,
+                            short_title: None,
+                            body_html: body_content.clone(),
+                            date: mdf.created_time.format(&self.date_format).to_string(),
+                            path: out_file_name.clone(),
+                            published: mdf.created_time,
+                            updated: mdf.modified_time,
+                            draft: mdf.front_matter.draft,
+                        };
+                        pages.push(page);
+
+                        if let Some(name) = mdf.front_matter.meta_str("author") {
+                            let author = author_slugs
+                                .entry(self.slug(name))
+                                .or_insert_with(|| name.to_string())
+                                .clone();
+                            if author != name {
+                                let source = format!("{}", mdf.file_path_buf.display());
+                                warn!(
+                                    "{} is by {:?}, whose name has the same slug as {:?}",
+                                    source, name, author
+                                );
+                                summary.problems.add(
+                                    &source,
+                                    None,
+                                    format!(
+                                        "its author {:?} is listed with {:?}, whose name has the same slug",
+                                        name, author
+                                    ),
+                                );
+                            }
+                            author_posts
+                                .entry(author)
+                                .or_default()
+                                .push(out_file_name.clone());
+                        }
+
+                        if self.report_changes {
+                            manifest.posts.insert(
+                                out_file_name.clone(),
+                                ManifestEntry {
+                                    source: format!("{}", mdf.file_path_buf.display()),
+                                    title: title.replace('"', ""),
+                                    content_hash: sha256_hex(mdf.md_content.as_bytes()),
+                                },
+                            );
+                        }
+
+                        let json_file_name = out_file_name.replace(".html", ".json");
+                        if self.json_output && is_reserved_file_name(&json_file_name) {
+                            let source = format!("{}", mdf.file_path_buf.display());
+                            warn!(
+                                "not writing {} for {}, the name is taken",
+                                json_file_name, source
+                            );
+                            summary.problems.add(
+                                &source,
+                                None,
+                                format!(
+                                    "its {} would overwrite the generator's own, rename the post",
+                                    json_file_name
+                                ),
+                            );
+                        } else if self.json_output {
+                            let json_post = JsonPost {
+                                title: raw_title.trim().to_string(),
+                                url: self.relative_url(0, &out_file_name),
+                                published: mdf.created_time.to_rfc3339(),
+                                updated: mdf.modified_time.to_rfc3339(),
+                                tags: mdf.front_matter.tags.clone(),
+                                excerpt: excerpt.clone(),
+                                body_html: body_content.clone(),
+                                meta: mdf.front_matter.meta.clone(),
+                            };
+                            let json_path =
+                                format!("{}/{}", &self.rendered_outputs_dir, &json_file_name);
+                            if changed.is_some() {
+                                self.write_output_if_changed(
+                                    &json_path,
+                                    &json_post.to_json(),
+                                    &mut summary,
+                                )?;
+                            } else {
+                                self.write_output(&json_path, &json_post.to_json(), &mut summary)?;
+                            }
+                            json_posts
+                                .push(json_post.summary(self.relative_url(0, &json_file_name)));
+                        }
+                    }
+                    Err(e) => {
+                        warn!("error writing rendered file: {}", e);
+                        summary.problems.add(
+                            &format!("{}", mdf.file_path_buf.display()),
+                            None,
+                            format!("could not be written: {}", e),
+                        );
+                        continue;
+                    }
+                }
+            } else {
+                return Err(BlogGeneratorError::TemplateUseError(
+                    template,
+                    "".to_string(),
+                ));
+            }
+        }
+
+        if let Some(progress_bar) = progress_bar {
+            progress_bar.finish_and_clear();
+        }
+
+        // the rest of the pages count only the posts that were written
+        tera.site.post_count = pages.len();
+
+        for (title, sources) in titles.iter().filter(|(_, sources)| sources.len() > 1) {
+            let sources = sources.join(", ");
+            if self.strict {
+                return Err(BlogGeneratorError::DuplicateTitle(title.clone(), sources));
+            }
+            warn!(
+                "the title {:?} is used by more than one markdown source file: {}",
+                title, sources
+            );
+        }
+
+        self.save_render_cache();
+
+        if self.report_changes {
+            self.write_change_report(&manifest, &mut summary)?;
+        }
+
+        if let Some(pdf_renderer) = &self.pdf_renderer {
+            let written: Vec<Page> = pages
+                .iter()
+                .filter(|page| written_pages.contains(&page.path))
+                .cloned()
+                .collect();
+            self.write_pdfs(pdf_renderer, &written, &mut summary);
+        }
+
+        self.write_redirects(&markdown_files_sorted, &pages, &mut summary)?;
+
+        if self.json_output {
+            let out_file = format!("{}/posts.json", &self.rendered_outputs_dir);
+            let json = serde_json::to_string_pretty(&json_posts).unwrap();
+            self.write_output(&out_file, &json, &mut summary)?;
+            self.print_status(None, format!("wrote {:?}", &out_file));
+        }
+
+        if self.bare && !self.bare_keep_index {
+            self.write_etags(&summary)?;
+            return Ok(summary);
+        }
+
+        if self.not_found_page {
+            self.render_not_found_page(&tera, &css_from_source, &mut summary)?;
+        }
+
+        self.add_template(
+            &mut tera.templates,
+            "index",
+            &self.index_template,
+            get_index_page_template(),
+        )?;
+        if self.archive {
+            self.add_template(
+                &mut tera.templates,
+                "archive",
+                &None,
+                get_archive_page_template(),
+            )?;
+        }
+
+        // generate a landing page for each series, listing its parts in order
+        for (series, parts) in series_parts.iter() {
+            self.render_listing_page(&tera, "series", series, parts, &pages, &mut summary)?;
+        }
+
+        // and one for each tag
+        for (tag, posts) in tag_posts.iter() {
+            self.render_listing_page(&tera, "tags", tag, posts, &pages, &mut summary)?;
+        }
+
+        // and one for each author
+        for (author, posts) in author_posts.iter() {
+            self.render_listing_page(&tera, "authors", author, posts, &pages, &mut summary)?;
+        }
+
+        // an index for each language, with the combined one below
+        for (lang, posts) in lang_posts.iter() {
+            let lang_pages: Vec<Page> = pages
+                .iter()
+                .filter(|page| posts.contains(&page.path))
+                .cloned()
+                .collect();
+
+            let mut context = self.index_context(&lang_pages, 0);
+            context.insert("lang", lang);
+
+            let rendered = tera.render("index", &context).map_err(|e| {
+                BlogGeneratorError::TemplateUseError("index".to_string(), e.to_string())
+            })?;
+
+            let out_file = format!(
+                "{}/{}",
+                &self.rendered_outputs_dir,
+                language_index_path(lang)
+            );
+            self.write_output(&out_file, &rendered, &mut summary)?;
+            self.print_status(None, format!("wrote {:?}", &out_file));
+        }
+
+        if self.archive {
+            self.render_archive_page(&tera, &markdown_files_sorted, &pages, &mut summary)?;
+        }
+
+        if self.single_page {
+            self.render_single_page(&tera, &css_from_source, &pages, &mut summary)?;
+        }
+
+        // generate an index page that contains links to all the pages, sorted by creation time
+
+        let mut context = self.index_context(&pages, 0);
+        if self.archive {
+            context.insert("archive_url", &self.relative_url(0, "archive.html"));
+        }
+        // the authors are only worth listing when there's more than one of them
+        if author_posts.len() > 1 {
+            let authors: Vec<AuthorIndex> = author_posts
+                .keys()
+                .map(|author| AuthorIndex {
+                    name: author.clone(),
+                    url: self.relative_url(0, &format!("authors/{}.html", self.slug(author))),
+                })
+                .collect();
+            context.insert("authors", &authors);
+        }
+        if self.tag_cloud {
+            let tag_cloud = tag_cloud(&tag_posts, |tag| {
+                self.relative_url(0, &format!("tags/{}.html", self.slug(tag)))
+            });
+            context.insert("tag_cloud", &tag_cloud);
+        }
+
+        let rendered = tera.render("index", &context);
+        if let Ok(rendered) = rendered {
+            let out_file = format!("{}/index.html", &self.rendered_outputs_dir);
+
+            self.write_output(&out_file, &rendered, &mut summary)?;
+        } else {
+            return Err(BlogGeneratorError::TemplateUseError(
+                "index".to_string(),
+                "".to_string(),
+            ));
+        }
+
+        if let Some(feed_format) = self.feed_format {
+            self.write_feeds(feed_format, &pages, &mut summary)?;
+        }
+
+        if let Some(recent_count) = self.recent_count {
+            self.write_recent_posts(recent_count, &pages, build_time, &mut summary)?;
+        }
+
+        if let Some(blogroll) = &self.blogroll {
+            self.write_blogroll(&tera, blogroll, &mut summary)?;
+        }
+
+        self.write_etags(&summary)?;
+
+        Ok(summary)
+    }
+}
+
+/// How long to wait before retrying a failed write for the `attempt`th time (from 0): a
+/// doubling wait from 100ms, up to `MAX_WRITE_BACKOFF`.
+fn write_backoff(attempt: u32) -> Duration {
+    Duration::from_millis(100u64.saturating_mul(2u64.saturating_pow(attempt)))
+        .min(MAX_WRITE_BACKOFF)
+}
+
+/// The created and modified times of a file, from its metadata. Not every filesystem (or
+/// OS) records when a file was created, so the modified time stands in for it, and the
+/// `fallback` for both when neither is known.
+fn file_times(
+    created: std::io::Result<SystemTime>,
+    modified: std::io::Result<SystemTime>,
+    fallback: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let modified: Option<DateTime<Utc>> = modified.ok().map(Into::into);
+    let created = created
+        .ok()
+        .map(Into::into)
+        .or(modified)
+        .unwrap_or(fallback);
+
+    (created, modified.unwrap_or(created))
+}
+
+/// Whether the generator writes a page (or other file) of its own at `path`, relative to
+/// the rendered outputs dir, whether or not it does in this build.
+fn is_generated_path(path: &str) -> bool {
+    const GENERATED_FILES: [&str; 17] = [
+        "index.html",
+        "archive.html",
+        "all.html",
+        "404.html",
+        "recent.html",
+        "blogroll.html",
+        "blogroll.opml",
+        "site.css",
+        "_redirects",
+        "feed.xml",
+        "atom.xml",
+        "feed.json",
+        "posts.json",
+        "manifest.json",
+        "changes.json",
+        "etags.json",
+        BUILD_LOCK_FILE,
+    ];
+    const GENERATED_DIRS: [&str; 4] = ["tags/", "series/", "authors/", "og/"];
+
+    GENERATED_FILES.contains(&path)
+        || GENERATED_DIRS.iter().any(|dir| path.starts_with(dir))
+        || (path.starts_with("index.") && path.ends_with(".html"))
+}
+
+/// The path of the index listing the posts in `lang`, relative to the rendered outputs dir.
+fn language_index_path(lang: &str) -> String {
+    format!("index.{}.html", slugify(lang))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn validate_css_source(css_source_file: &str) -> Result<(), BlogGeneratorError> {
+    match fs::metadata(css_source_file) {
+        Err(e) => Err(BlogGeneratorError::InvalidCSSPath(
+            css_source_file.to_string(),
+            e.to_string(),
+        )),
+        Ok(metadata) if !metadata.is_file() => Err(BlogGeneratorError::InvalidCSSPath(
+            css_source_file.to_string(),
+            "expected a file, found a directory".to_string(),
+        )),
+        Ok(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator() -> BlogGenerator {
+        let css = format!("{}/css_sources/retro.css", env!("CARGO_MANIFEST_DIR"));
+        BlogGenerator::new_standalone("https://example.com/".to_string(), css).unwrap()
+    }
+
+    #[test]
+    fn the_modified_time_stands_in_for_an_unknown_created_time() {
+        let unknown = || Err(std::io::Error::from(std::io::ErrorKind::Unsupported));
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let fallback = DateTime::<Utc>::from(SystemTime::UNIX_EPOCH);
+
+        let (created, _) = file_times(unknown(), Ok(modified), fallback);
+        assert_eq!(created, DateTime::<Utc>::from(modified));
+        assert_eq!(
+            file_times(unknown(), unknown(), fallback),
+            (fallback, fallback)
+        );
+        let (_, modified_time) = file_times(Ok(modified), unknown(), fallback);
+        assert_eq!(modified_time, DateTime::<Utc>::from(modified));
+    }
+
+    #[test]
+    fn write_backoffs_double_up_to_a_limit() {
+        assert_eq!(write_backoff(0), Duration::from_millis(100));
+        assert_eq!(write_backoff(3), Duration::from_millis(800));
+        assert_eq!(write_backoff(10), MAX_WRITE_BACKOFF);
+        assert_eq!(write_backoff(u32::MAX), MAX_WRITE_BACKOFF);
+        assert_eq!(
+            generator().with_write_retries(1000).write_retries,
+            MAX_WRITE_RETRIES
+        );
+    }
+
+    #[test]
+    fn bare_rendering_still_checks_the_css() {
+        let dir = std::env::temp_dir().join(format!("md-blog-gen-bare-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let css = dir.join("style.css");
+        fs::write(&css, "@import \"missing.css\";\n").unwrap();
+
+        let generator = BlogGenerator::new_standalone("./".to_string(), css.display().to_string())
+            .unwrap()
+            .with_resolve_css_imports(true)
+            .with_bare(true, false);
+        assert!(generator.render_str("# Hi\n").is_err());
+
+        fs::write(&css, "body {}\n").unwrap();
+        assert_eq!(generator.render_str("# Hi\n").unwrap(), "<h1>Hi</h1>\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn disabling_raw_html_drops_it() {
+        let generator = generator().with_disable_raw_html(true);
+        let html = generator.markdown_to_html(
+            "# Title\n\n<div>raw</div>\n\ntext\n",
+            &FrontMatter::default(),
+        );
+        assert!(!html.contains("<div>"));
+        assert!(html.contains("<p>text</p>"));
+    }
+
+    #[test]
+    fn typography_can_be_turned_off_per_post() {
+        let md = "# Title\n\nIt's \"quoted\" -- here\n";
+        let smart = generator().markdown_to_html(md, &FrontMatter::default());
+        assert!(smart.contains("It\u{2019}s \u{201c}quoted\u{201d} \u{2013} here"));
+
+        let front_matter = FrontMatter {
+            typography: Some(false),
+            ..FrontMatter::default()
+        };
+        let plain = generator().markdown_to_html(md, &front_matter);
+        assert!(plain.contains("It's &quot;quoted&quot; -- here"));
+    }
+
+    #[test]
+    fn disabling_raw_html_keeps_shortcodes() {
+        let generator = generator().with_disable_raw_html(true);
+        let (md_content, _) = expand_shortcodes("# Title\n\n{{< video src=\"clip.mp4\" >}}\n");
+        let html = generator.markdown_to_html(&md_content, &FrontMatter::default());
+        assert!(html.contains("<video controls src=\"clip.mp4\""));
+    }
+
+    /// A scratch site with the given posts, returning its directory and its sources and
+    /// outputs directories within it.
+    fn site(name: &str, posts: &[(&str, &str)]) -> (PathBuf, PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("md-blog-gen-{}-{}", name, std::process::id()));
+        let (src, out) = (dir.join("src"), dir.join("out"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        for (file_name, contents) in posts {
+            fs::write(src.join(file_name), contents).unwrap();
+        }
+        (dir, src, out)
+    }
+
+    fn site_generator(src: &Path, out: &Path) -> BlogGenerator {
+        let css = format!("{}/css_sources/retro.css", env!("CARGO_MANIFEST_DIR"));
+        BlogGenerator::new(
+            "https://example.com/".to_string(),
+            css,
+            src.display().to_string(),
+            out.display().to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn data_title_is_escaped_once() {
+        let (dir, src, out) = site("data-title", &[("a.md", "# A & B \\ C\n\na\n")]);
+        site_generator(&src, &out)
+            .with_data_attributes(true)
+            .render()
+            .unwrap();
+        let page = fs::read_to_string(out.join("a.html")).unwrap();
+        assert!(page.contains("data-title=\"A &amp; B \\ C\""));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn critical_css_is_swapped_in_by_a_script_with_a_csp_nonce() {
+        let (dir, src, out) = site("critical-css", &[("a.md", "# A\n\na\n")]);
+        let generator = site_generator(&src, &out).with_critical_css(Some("body {}".to_string()));
+        generator.render().unwrap();
+        let page = fs::read_to_string(out.join("a.html")).unwrap();
+        assert!(page.contains("onload=\"this.media='all'\""));
+
+        let generator = generator.with_csp_nonce(Some("abc".to_string()));
+        generator.render().unwrap();
+        let page = fs::read_to_string(out.join("a.html")).unwrap();
+        assert!(!page.contains("onload="));
+        assert!(page.contains("<script nonce=\"abc\">\nvar siteStylesheet"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn css_variants_are_under_their_own_base_url() {
+        let (dir, src, out) = site("css-variants", &[("a.md", "# A\n\na\n")]);
+        let css = format!("{}/css_sources/retro.css", env!("CARGO_MANIFEST_DIR"));
+        site_generator(&src, &out)
+            .with_canonical_urls(true)
+            .render_css_variants(&[
+                ("light".to_string(), css.clone()),
+                ("dark".to_string(), css),
+            ])
+            .unwrap();
+        let page = fs::read_to_string(out.join("dark").join("a.html")).unwrap();
+        assert!(page.contains("<link rel=\"canonical\" href=\"https://example.com/dark/a.html\">"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tags_with_the_same_slug_share_a_page() {
+        let (dir, src, out) = site(
+            "tags",
+            &[
+                ("a.md", "---\ntags: [Rust]\n---\n# A\n\na\n"),
+                ("b.md", "---\ntags: [rust, Rust]\n---\n# B\n\nb\n"),
+            ],
+        );
+        site_generator(&src, &out)
+            .with_tag_cloud(true)
+            .render()
+            .unwrap();
+        assert_eq!(fs::read_dir(out.join("tags")).unwrap().count(), 1);
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert_eq!(index.matches("tags/rust.html").count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recent_posts_leave_out_drafts_and_scheduled_posts() {
+        let (dir, src, out) = site(
+            "recent",
+            &[
+                ("a.md", "---\ndate: 2024-01-01\n---\n# Published\n\na\n"),
+                (
+                    "b.md",
+                    "---\ndate: 2024-01-02\ndraft: true\n---\n# Draft\n\nb\n",
+                ),
+                ("c.md", "---\ndate: 2999-01-01\n---\n# Scheduled\n\nc\n"),
+            ],
+        );
+        site_generator(&src, &out)
+            .with_drafts(true)
+            .with_publish_future(true)
+            .with_recent_posts(Some(10))
+            .render()
+            .unwrap();
+        assert!(out.join("b.html").is_file() && out.join("c.html").is_file());
+        let recent = fs::read_to_string(out.join("recent.html")).unwrap();
+        assert!(recent.contains("a.html"));
+        assert!(!recent.contains("b.html") && !recent.contains("c.html"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn aliases_outside_the_site_or_taken_by_generated_pages_are_left_out() {
+        let (dir, src, out) = site(
+            "aliases",
+            &[(
+                "a.md",
+                "---\naliases: [old.html, ../escape.html, index.html, tags/rust.html]\n---\n# A\n\na\n",
+            )],
+        );
+        let summary = site_generator(&src, &out).render().unwrap();
+        assert_eq!(summary.redirects_written, 1);
+        assert!(out.join("old.html").is_file());
+        assert!(!dir.join("escape.html").exists());
+        assert!(!fs::read_to_string(out.join("index.html"))
+            .unwrap()
+            .contains("Redirecting"));
+        let problems = summary.problems.to_string();
+        assert!(problems.contains("../escape.html isn't a path within the site"));
+        assert!(problems.contains("taken by the generated index.html"));
+        assert!(problems.contains("taken by the generated tags/rust.html"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn posts_without_a_title_are_not_listed() {
+        let (dir, src, out) = site(
+            "list",
+            &[("a.md", "# A\n\na\n"), ("b.md", "no title heading\n")],
+        );
+        let list = site_generator(&src, &out).list_posts().unwrap();
+        assert_eq!(list.posts.len(), 1);
+        assert_eq!(list.posts[0].title, "A");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn author_pages_list_only_the_posts_written() {
+        let (dir, src, out) = site(
+            "authors",
+            &[
+                ("a.md", "---\nauthor: Ann Lee\n---\n# A\n\na\n"),
+                ("b.md", "---\nauthor: ann lee\n---\n# B\n\nb\n"),
+                ("c.md", "---\nauthor: Bob\n---\nno title heading\n"),
+            ],
+        );
+        let summary = site_generator(&src, &out).render().unwrap();
+        let authors: Vec<_> = fs::read_dir(out.join("authors"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(authors.len(), 1);
+        let page = fs::read_to_string(out.join("authors").join(&authors[0])).unwrap();
+        assert!(page.contains("a.html") && page.contains("b.html"));
+        assert!(summary
+            .problems
+            .to_string()
+            .contains("whose name has the same slug"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn incremental_rebuilds_write_only_the_pages_that_changed() {
+        let (dir, src, out) = site(
+            "incremental",
+            &[
+                ("a.md", "---\nseries: S\n---\n# Part A\n\na\n"),
+                ("b.md", "---\nseries: S\n---\n# Part B\n\nb\n"),
+                ("c.md", "# Other\n\nc\n"),
+            ],
+        );
+        let generator = site_generator(&src, &out);
+        generator.render().unwrap();
+        assert!(fs::read_to_string(out.join("b.html"))
+            .unwrap()
+            .contains("a.html"));
+
+        // moving a out of the series changes b's page too, but not c's
+        fs::write(src.join("a.md"), "# Part A\n\na\n").unwrap();
+        let summary = generator.render_changed(&[src.join("a.md")]).unwrap();
+        assert_eq!(summary.posts_written, 2);
+        assert!(!fs::read_to_string(out.join("b.html"))
+            .unwrap()
+            .contains("a.html"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn post_count_leaves_out_the_skipped_posts() {
+        let (dir, src, out) = site(
+            "post-count",
+            &[("a.md", "# A\n\na\n"), ("b.md", "no title\n")],
+        );
+        let template = dir.join("count.html");
+        fs::write(&template, "{{ site.post_count }}").unwrap();
+        let template = Some(template.display().to_string());
+        site_generator(&src, &out)
+            .with_templates(template.clone(), template)
+            .render()
+            .unwrap();
+        assert_eq!(fs::read_to_string(out.join("a.html")).unwrap(), "1");
+        assert_eq!(fs::read_to_string(out.join("index.html")).unwrap(), "1");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// The file names of the posts linked from the index, in the order they're listed.
+    fn index_order(out: &Path) -> Vec<String> {
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        let links = Selector::parse(".row-item a").unwrap();
+        Html::parse_document(&index)
+            .select(&links)
+            .filter_map(|a| a.value().attr("href")?.rsplit('/').next())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn posts_with_the_same_date_are_ordered_by_file_name() {
+        let post = |title| format!("---\ndate: 2020-01-01\n---\n# {}\n", title);
+        let (dir, src, out) = site(
+            "same-date",
+            &[
+                ("b.md", &post("B")),
+                ("c.md", &post("C")),
+                ("a.md", &post("A")),
+            ],
+        );
+        site_generator(&src, &out).render().unwrap();
+        assert_eq!(index_order(&out), ["a.html", "b.html", "c.html"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_css_path_that_is_a_directory_is_rejected() {
+        let css = format!("{}/css_sources", env!("CARGO_MANIFEST_DIR"));
+        let generator = BlogGenerator::new_standalone("https://example.com/".to_string(), css);
+        assert!(matches!(
+            generator,
+            Err(BlogGeneratorError::InvalidCSSPath(_, reason)) if reason.contains("directory")
+        ));
+    }
+
+    #[test]
+    fn empty_files_are_skipped_and_reported() {
+        let (dir, src, out) = site("empty", &[("a.md", "# A\n\na\n"), ("b.md", " \n\n")]);
+        let generator = site_generator(&src, &out);
+        let summary = generator.render().unwrap();
+        assert_eq!(
+            summary.empty_files,
+            [src.join("b.md").display().to_string()]
+        );
+        assert!(summary.to_string().contains("empty files skipped: 1"));
+        assert!(out.join("a.html").exists());
+        assert!(!out.join("b.html").exists());
+
+        assert!(matches!(
+            generator.with_strict(true).render(),
+            Err(BlogGeneratorError::EmptyMarkDownFile(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_post_can_override_a_shared_link_ref() {
+        let generator = generator().with_link_refs(Some(
+            "[docs]: https://docs.example.com/\n[home]: https://example.com/".to_string(),
+        ));
+        let html = generator
+            .render_str("# A\n\n[docs] and [home]\n\n[docs]: https://mine.example.com/\n")
+            .unwrap();
+        assert!(html.contains("<a href=\"https://mine.example.com/\">docs</a>"));
+        assert!(html.contains("<a href=\"https://example.com/\">home</a>"));
+        assert!(!html.contains("https://docs.example.com/"));
+    }
+
+    #[test]
+    fn broken_front_matter_names_the_file() {
+        let (dir, src, out) = site("broken-yaml", &[("a.md", "---\ntags: [a\n---\n# A\n")]);
+        let generator = site_generator(&src, &out);
+        let summary = generator.render().unwrap();
+        assert!(summary
+            .to_string()
+            .contains(&src.join("a.md").display().to_string()));
+
+        let e = generator.with_strict(true).render().unwrap_err();
+        assert!(matches!(e, BlogGeneratorError::FrontMatterError(..)));
+        let message = e.to_string();
+        assert!(message.contains(&src.join("a.md").display().to_string()));
+        assert!(message.contains("line "));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn crlf_sources_keep_their_front_matter_and_excerpt() {
+        let md =
+            "---\r\ntags: [crlf]\r\n---\r\n# A\r\n\r\nintro\r\n\r\n<!-- more -->\r\n\r\nrest\r\n";
+        let (dir, src, out) = site("crlf", &[("a.md", md)]);
+        site_generator(&src, &out)
+            .with_index_excerpts(true)
+            .render()
+            .unwrap();
+        assert!(out.join("tags/crlf.html").exists());
+        let page = fs::read_to_string(out.join("a.html")).unwrap();
+        assert!(!page.contains("tags: [crlf]"));
+        assert!(!page.contains("<!-- more -->"));
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(index.contains("<p>intro</p>"));
+        assert!(!index.contains("<p>rest</p>"));
+        assert!(index.contains("class=\"read-more\""));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn posts_without_an_order_come_after_those_with_one_by_date() {
+        let (dir, src, out) = site(
+            "order",
+            &[
+                ("a.md", "---\norder: 2\ndate: 2019-01-01\n---\n# A\n"),
+                ("b.md", "---\ndate: 2020-01-01\n---\n# B\n"),
+                ("c.md", "---\norder: 1\ndate: 2021-01-01\n---\n# C\n"),
+                ("d.md", "---\ndate: 2018-01-01\n---\n# D\n"),
+            ],
+        );
+        let generator = site_generator(&src, &out);
+        generator.render().unwrap();
+        assert_eq!(index_order(&out), ["d.html", "a.html", "b.html", "c.html"]);
+        generator
+            .with_post_order(PostOrder::Order)
+            .render()
+            .unwrap();
+        assert_eq!(index_order(&out), ["c.html", "a.html", "d.html", "b.html"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn allow_html_keeps_the_raw_html_of_just_that_post() {
+        let iframe = "<iframe src=\"https://example.com/embed\"></iframe>";
+        let (dir, src, out) = site(
+            "allow-html",
+            &[
+                (
+                    "a.md",
+                    &format!("---\nallow_html: true\n---\n# A\n\n{}\n", iframe),
+                ),
+                ("b.md", &format!("# B\n\n{}\n", iframe)),
+            ],
+        );
+        site_generator(&src, &out)
+            .with_disable_raw_html(true)
+            .render()
+            .unwrap();
+        assert!(fs::read_to_string(out.join("a.html"))
+            .unwrap()
+            .contains(iframe));
+        assert!(!fs::read_to_string(out.join("b.html"))
+            .unwrap()
+            .contains("<iframe"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_table_of_contents_doesnt_make_a_long_read() {
+        let (dir, src, out) = site(
+            "long-read",
+            &[
+                ("short.md", "# A\n\n## Alpha beta\n\n## Gamma delta\n"),
+                ("long.md", "# B\n\none two three four five six seven\n"),
+            ],
+        );
+        let template = dir.join("post.html");
+        fs::write(&template, "{{ word_count }} {{ long_read }}").unwrap();
+        site_generator(&src, &out)
+            .with_templates(Some(template.display().to_string()), None)
+            .with_toc(true, 3)
+            .with_long_read_threshold(6)
+            .render()
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("short.html")).unwrap(),
+            "5 false"
+        );
+        assert_eq!(fs::read_to_string(out.join("long.html")).unwrap(), "8 true");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn long_titles_are_shortened_on_the_index() {
+        let (dir, src, out) = site(
+            "short-title",
+            &[
+                ("a.md", "# Ünïcödé títlé\n\na\n"),
+                ("b.md", "# A & B \\<Ünïcödé\\>\n\nb\n"),
+                ("c.md", "# x \\<y\\>\n\nc\n"),
+            ],
+        );
+        site_generator(&src, &out)
+            .with_index_title_max_chars(Some(6))
+            .render()
+            .unwrap();
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(index.contains("title=\"Ünïcödé títlé\">Ünïcö\u{2026}</a>"));
+        assert!(index.contains("title=\"A &amp; B &lt;Ünïcödé&gt;\">A &amp; B\u{2026}</a>"));
+        // titles short enough to be shown in full are escaped too
+        assert!(index.contains(">x &lt;y&gt;</a>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn numbered_headings_keep_their_ids() {
+        let generator = generator().with_toc(true, 3).with_number_headings(true);
+        let md = "# Title\n\n## First\n\n### Inner\n";
+        let html = generator.markdown_to_html(md, &FrontMatter::default());
+        assert!(
+            html.contains("<h2 id=\"first\"><span class=\"heading-number\">1</span> First</h2>")
+        );
+        assert!(
+            html.contains("<h3 id=\"inner\"><span class=\"heading-number\">1.1</span> Inner</h3>")
+        );
+        assert!(
+            html.contains("<a href=\"#inner\"><span class=\"heading-number\">1.1</span> Inner</a>")
+        );
+
+        let front_matter = FrontMatter {
+            number_headings: Some(false),
+            ..FrontMatter::default()
+        };
+        let html = generator.markdown_to_html(md, &front_matter);
+        assert!(!html.contains("heading-number"));
+        assert!(html.contains("<h2 id=\"first\">First</h2>"));
+    }
+
+    #[test]
+    fn the_bom_starts_each_page_once() {
+        let (dir, src, out) = site("bom", &[("a.md", "\u{feff}# A\n\na\n")]);
+        site_generator(&src, &out)
+            .with_output_format(false, None, true)
+            .with_json_output(true)
+            .render()
+            .unwrap();
+        for page in ["a.html", "index.html"] {
+            let page = fs::read_to_string(out.join(page)).unwrap();
+            assert!(page.starts_with('\u{feff}'));
+            assert_eq!(page.matches('\u{feff}').count(), 1);
+        }
+        let posts = fs::read_to_string(out.join("posts.json")).unwrap();
+        assert!(!posts.contains('\u{feff}'));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// The contents of the only page written into `dir`.
+    fn only_page(dir: &Path) -> String {
+        let pages: Vec<PathBuf> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(pages.len(), 1);
+        fs::read_to_string(&pages[0]).unwrap()
+    }
+
+    #[test]
+    fn series_names_are_escaped() {
+        let post = |title| format!("---\nseries: Rust <T> & generics\n---\n# {}\n\na\n", title);
+        let (dir, src, out) = site(
+            "series-escape",
+            &[("a.md", &post("A")), ("b.md", &post("B"))],
+        );
+        site_generator(&src, &out).render().unwrap();
+        let page = fs::read_to_string(out.join("a.html")).unwrap();
+        assert!(page.contains(">Rust &lt;T&gt; &amp; generics</a>"));
+        let series_page = only_page(&out.join("series"));
+        assert!(series_page.contains("<h1>Rust &lt;T&gt; &amp; generics</h1>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tag_names_are_escaped_on_their_page() {
+        let (dir, src, out) = site(
+            "tag-escape",
+            &[("a.md", "---\ntags: [c++ & rust]\n---\n# A\n\na\n")],
+        );
+        site_generator(&src, &out).render().unwrap();
+        let tag_page = only_page(&out.join("tags"));
+        assert!(tag_page.contains("<h1>c++ &amp; rust</h1>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn author_names_are_escaped_on_their_page() {
+        let (dir, src, out) = site(
+            "author-escape",
+            &[(
+                "a.md",
+                "---\nauthor: Ada <ada@example.com>\n---\n# A\n\na\n",
+            )],
+        );
+        site_generator(&src, &out).render().unwrap();
+        let author_page = only_page(&out.join("authors"));
+        assert!(author_page.contains("<h1>Ada &lt;ada@example.com&gt;</h1>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn archive_titles_are_escaped() {
+        let (dir, src, out) = site("archive-escape", &[("a.md", "# Vec\\<T\\> & more\n\na\n")]);
+        site_generator(&src, &out)
+            .with_archive(true)
+            .render()
+            .unwrap();
+        let archive = fs::read_to_string(out.join("archive.html")).unwrap();
+        assert!(archive.contains(">Vec&lt;T&gt; &amp; more</a>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_heading_offset_of_zero_leaves_the_html_as_it_is() {
+        let md = "# Title\n\n## Section\n\n### Subsection\n\ntext\n";
+        let generator = generator().with_heading_offset(0);
+        let front_matter = FrontMatter::default();
+        let mut expected = String::new();
+        html::push_html(
+            &mut expected,
+            Parser::new_ext(md, generator.markdown_options(&front_matter)),
+        );
+        assert_eq!(generator.markdown_to_html(md, &front_matter), expected);
+    }
+
+    #[test]
+    fn a_heading_offset_shifts_the_headings_and_the_title_is_still_found() {
+        let generator = generator().with_heading_offset(1);
+        let front_matter = FrontMatter::default();
+        let html = generator.markdown_to_html("# Title\n\n## Section\n", &front_matter);
+        assert!(html.contains("<h2>Title</h2>"));
+        assert!(html.contains("<h3>Section</h3>"));
+        assert!(!html.contains("<h1>"));
+        assert_eq!(
+            generator.post_title(&front_matter, &html).as_deref(),
+            Some("Title")
+        );
+    }
+
+    #[test]
+    fn a_heading_offset_stops_at_h6() {
+        let generator = generator().with_heading_offset(2);
+        let html = generator.markdown_to_html("#### Four\n\n###### Six\n", &FrontMatter::default());
+        assert!(html.contains("<h6>Four</h6>"));
+        assert!(html.contains("<h6>Six</h6>"));
+        assert!(!html.contains("<h7>"));
+    }
 }
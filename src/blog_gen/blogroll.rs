@@ -0,0 +1,65 @@
+use std::fs;
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::Deserialize;
+
+use super::blog_generator::BlogGeneratorError;
+
+/// One of the blogs on the blogroll.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BlogrollEntry {
+    pub title: String,
+    pub feed_url: String,
+    /// the home page of the blog, which the blogroll page links to (or else to its feed)
+    #[serde(default)]
+    pub site_url: Option<String>,
+}
+
+/// The other blogs recommended by this one, read from a YAML file listing them:
+///
+/// ```text
+/// - title: Another Blog
+///   feed_url: https://another.example.com/feed.xml
+///   site_url: https://another.example.com/
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Blogroll {
+    pub entries: Vec<BlogrollEntry>,
+}
+
+impl Blogroll {
+    pub fn load(blogroll_file: &str) -> Result<Self, BlogGeneratorError> {
+        let invalid =
+            |detail: String| BlogGeneratorError::InvalidBlogroll(blogroll_file.to_string(), detail);
+        let yaml = fs::read_to_string(blogroll_file).map_err(|e| invalid(e.to_string()))?;
+        let entries: Vec<BlogrollEntry> =
+            serde_yaml::from_str(&yaml).map_err(|e| invalid(e.to_string()))?;
+
+        Ok(Blogroll { entries })
+    }
+
+    /// The blogroll as an OPML 2.0 outline of the feeds, for importing into feed readers.
+    pub fn to_opml(&self, title: &str) -> String {
+        let mut opml = String::new();
+        opml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        opml.push_str("<opml version=\"2.0\">\n<head>\n");
+        opml.push_str(&format!("<title>{}</title>\n", encode_text(title)));
+        opml.push_str("</head>\n<body>\n");
+        for entry in self.entries.iter() {
+            let site_url = entry
+                .site_url
+                .as_deref()
+                .map(|url| format!(" htmlUrl=\"{}\"", encode_double_quoted_attribute(url)))
+                .unwrap_or_default();
+            opml.push_str(&format!(
+                "<outline type=\"rss\" text=\"{title}\" title=\"{title}\" xmlUrl=\"{}\"{}/>\n",
+                encode_double_quoted_attribute(&entry.feed_url),
+                site_url,
+                title = encode_double_quoted_attribute(&entry.title),
+            ));
+        }
+        opml.push_str("</body>\n</opml>\n");
+
+        opml
+    }
+}
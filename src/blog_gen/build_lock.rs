@@ -0,0 +1,149 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::Utc;
+use log::warn;
+
+/// The lock file in the rendered outputs dir held for the duration of a build.
+pub const BUILD_LOCK_FILE: &str = ".build.lock";
+
+/// How long a lock can go without being refreshed before it's taken to be left behind by
+/// a build that crashed, rather than held by one that's still running.
+pub const STALE_LOCK_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// A lock on a rendered outputs dir, keeping two builds from writing into it at the same
+/// time. The lock file holds the process id of the build and when it started, and is
+/// removed when the lock is dropped. The build refreshes it as it goes, so a long build
+/// doesn't look stale.
+#[derive(Debug)]
+pub struct BuildLock {
+    path: PathBuf,
+}
+
+impl BuildLock {
+    /// Takes the lock on `dir`, failing if another build holds it. A lock left behind by a
+    /// build that's no longer running (older than `STALE_LOCK_AGE`, or on Linux, of a
+    /// process that has exited) is taken over.
+    pub fn acquire(dir: &Path) -> Result<BuildLock, String> {
+        let path = dir.join(BUILD_LOCK_FILE);
+
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}\n{}", std::process::id(), Utc::now().to_rfc3339())
+                        .map_err(|e| e.to_string())?;
+                    return Ok(BuildLock { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).unwrap_or_default();
+                    let mut lines = holder.lines();
+                    let (pid, started) = (lines.next(), lines.next());
+                    if !is_stale(&path, pid) {
+                        return Err(format!(
+                            "another build (process {}, started {}) holds it; if no build is \
+                             running, remove it",
+                            pid.unwrap_or("unknown"),
+                            started.unwrap_or("at an unknown time")
+                        ));
+                    }
+                    warn!(
+                        "taking over the lock {} left behind by process {}",
+                        path.display(),
+                        pid.unwrap_or("unknown")
+                    );
+                    fs::remove_file(&path).map_err(|e| e.to_string())?;
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        Err("it was taken by another build at the same time".to_string())
+    }
+
+    /// Marks the lock as still held by a running build, by updating its modified time.
+    pub fn refresh(&self) {
+        let refreshed = OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        if let Err(e) = refreshed {
+            warn!("could not refresh the lock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("could not remove the lock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Whether the lock at `path` was left behind, by the time since it was last refreshed or
+/// by its process `pid` having exited.
+fn is_stale(path: &Path, pid: Option<&str>) -> bool {
+    let age = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if age.is_some_and(|age| age > STALE_LOCK_AGE) {
+        return true;
+    }
+
+    // every running process has a dir in /proc
+    cfg!(target_os = "linux")
+        && Path::new("/proc/self").exists()
+        && pid
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+            .is_some_and(|pid| !Path::new(&format!("/proc/{}", pid)).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("md-blog-gen-lock-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_held_lock_cant_be_taken_until_it_is_dropped() {
+        let dir = lock_dir("held");
+        let lock = BuildLock::acquire(&dir).unwrap();
+        assert!(BuildLock::acquire(&dir).is_err());
+
+        drop(lock);
+        assert!(!dir.join(BUILD_LOCK_FILE).exists());
+        assert!(BuildLock::acquire(&dir).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refreshing_keeps_an_old_lock_from_going_stale() {
+        let dir = lock_dir("refresh");
+        let lock = BuildLock::acquire(&dir).unwrap();
+        let path = dir.join(BUILD_LOCK_FILE);
+        let old = SystemTime::now() - STALE_LOCK_AGE * 2;
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        assert!(is_stale(&path, None));
+
+        lock.refresh();
+        assert!(!is_stale(&path, None));
+
+        drop(lock);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
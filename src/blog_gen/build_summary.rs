@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::manifest::ChangeReport;
+use super::problems::ProblemReport;
+
+/// Statistics collected over the course of a single `BlogGenerator::render` call.
+#[derive(Clone, Debug, Default)]
+pub struct BuildSummary {
+    pub posts_written: usize,
+
+    /// only populated when the missing alt text check is enabled
+    pub images_missing_alt: Option<usize>,
+
+    /// only populated when copying images
+    pub images_copied: Option<usize>,
+
+    /// only populated when generating WebP versions of copied images
+    pub webp_images_generated: Option<usize>,
+
+    /// only populated when generating Open Graph images
+    pub og_images_generated: Option<usize>,
+
+    /// total size of the `.gz` files written, when pre-compressing with gzip
+    pub gzip_bytes: Option<u64>,
+
+    /// total size of the `.br` files written, when pre-compressing with brotli
+    pub brotli_bytes: Option<u64>,
+
+    /// only populated when printing the posts to PDFs
+    pub pdfs_written: Option<usize>,
+
+    /// pages written at the old paths of posts, redirecting to them
+    pub redirects_written: usize,
+
+    /// posts left out because their date is still in the future, with their scheduled date
+    pub posts_scheduled: Vec<(String, String)>,
+
+    /// posts left out for being drafts
+    pub drafts_skipped: Vec<String>,
+
+    /// posts left out for being dated before the `since` cutoff
+    pub posts_before_since: Vec<String>,
+
+    /// markdown sources skipped for being over the maximum file size
+    pub oversized_files: Vec<String>,
+
+    /// markdown sources skipped for being empty (or only whitespace)
+    pub empty_files: Vec<String>,
+
+    /// the sha256 of each page written (keyed by its path in the rendered outputs dir),
+    /// when generating etags
+    pub etags: Option<BTreeMap<String, String>>,
+
+    /// posts whose rendered markdown was (or wasn't) taken from the cache, only
+    /// populated when caching
+    pub cache_hits: Option<usize>,
+    pub cache_misses: Option<usize>,
+
+    /// how the posts differ from the previous build, only populated when reporting changes
+    pub changes: Option<ChangeReport>,
+
+    /// problems with the markdown sources that didn't stop the build, by source file
+    pub problems: ProblemReport,
+}
+
+impl fmt::Display for BuildSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "posts written: {}", self.posts_written)?;
+
+        if let Some(images_missing_alt) = self.images_missing_alt {
+            write!(f, "\nimages missing alt text: {}", images_missing_alt)?;
+        }
+
+        if let Some(images_copied) = self.images_copied {
+            write!(f, "\nimages copied: {}", images_copied)?;
+        }
+
+        if let Some(webp_images_generated) = self.webp_images_generated {
+            write!(f, "\nwebp images generated: {}", webp_images_generated)?;
+        }
+
+        if let Some(og_images_generated) = self.og_images_generated {
+            write!(f, "\nopen graph images generated: {}", og_images_generated)?;
+        }
+
+        if let Some(gzip_bytes) = self.gzip_bytes {
+            write!(f, "\ngzip compressed size: {} bytes", gzip_bytes)?;
+        }
+
+        if let Some(brotli_bytes) = self.brotli_bytes {
+            write!(f, "\nbrotli compressed size: {} bytes", brotli_bytes)?;
+        }
+
+        if let Some(pdfs_written) = self.pdfs_written {
+            write!(f, "\npdfs written: {}", pdfs_written)?;
+        }
+
+        if self.redirects_written > 0 {
+            write!(f, "\nredirects written: {}", self.redirects_written)?;
+        }
+
+        if !self.posts_scheduled.is_empty() {
+            write!(f, "\nposts scheduled: {}", self.posts_scheduled.len())?;
+            for (source, date) in &self.posts_scheduled {
+                write!(f, "\n  {} (on {})", source, date)?;
+            }
+        }
+
+        if !self.drafts_skipped.is_empty() {
+            write!(f, "\ndrafts skipped: {}", self.drafts_skipped.len())?;
+            for source in &self.drafts_skipped {
+                write!(f, "\n  {}", source)?;
+            }
+        }
+
+        if !self.posts_before_since.is_empty() {
+            write!(
+                f,
+                "\nolder posts left out: {}",
+                self.posts_before_since.len()
+            )?;
+        }
+
+        if let Some(etags) = &self.etags {
+            write!(f, "\netags generated: {}", etags.len())?;
+        }
+
+        if !self.oversized_files.is_empty() {
+            write!(
+                f,
+                "\noversized files skipped: {}",
+                self.oversized_files.len()
+            )?;
+            for source in &self.oversized_files {
+                write!(f, "\n  {}", source)?;
+            }
+        }
+
+        if !self.empty_files.is_empty() {
+            write!(f, "\nempty files skipped: {}", self.empty_files.len())?;
+            for source in &self.empty_files {
+                write!(f, "\n  {}", source)?;
+            }
+        }
+
+        if let Some(changes) = &self.changes {
+            write!(f, "\n{}", changes)?;
+        }
+
+        if !self.problems.is_empty() {
+            write!(f, "\n{}", self.problems)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+/// The line separating the posts in a bundle file.
+pub const BUNDLE_DELIMITER: &str = "---8<---";
+
+/// Splits a bundle file into the markdown (front matter included) of each post, leaving
+/// out any sections that are empty.
+pub fn split_bundle(bundle: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in bundle.split_inclusive('\n') {
+        if line.trim_end() == BUNDLE_DELIMITER {
+            sections.push(&bundle[start..offset]);
+            start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    sections.push(&bundle[start..]);
+
+    sections
+        .into_iter()
+        .filter(|section| !section.trim().is_empty())
+        .collect()
+}
@@ -0,0 +1,127 @@
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use scraper::{Html, Selector};
+
+/// The result of scanning a rendered post for `<img>` tags without alt text.
+#[derive(Clone, Debug, Default)]
+pub struct AltTextReport {
+    /// the `src` of every image that has no `alt` attribute at all
+    pub missing: Vec<String>,
+
+    /// the number of images with an explicitly empty `alt=""`, which marks them as decorative
+    pub decorative: usize,
+}
+
+/// Scans the rendered html for images lacking alt text.
+/// An empty `alt` attribute is treated as an intentionally decorative image, and only
+/// an absent (or whitespace-only) one is reported as missing.
+pub fn find_images_missing_alt(html: &str) -> AltTextReport {
+    let mut report = AltTextReport::default();
+
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("img").unwrap();
+
+    for img in fragment.select(&selector) {
+        let src = img.value().attr("src").unwrap_or("").to_string();
+        match img.value().attr("alt") {
+            Some("") => report.decorative += 1,
+            Some(alt) if !alt.trim().is_empty() => {}
+            _ => report.missing.push(src),
+        }
+    }
+
+    report
+}
+
+/// A heading out of place in the hierarchy of a post's headings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeadingProblem {
+    /// a heading more than one level below the one before it, as in an `<h2>` directly
+    /// followed by an `<h4>`, with the levels and text of both
+    SkippedLevel {
+        from: (usize, String),
+        to: (usize, String),
+    },
+
+    /// the title heading level and the number of headings at it, when that isn't one
+    TitleCount(usize, usize),
+}
+
+impl std::fmt::Display for HeadingProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadingProblem::SkippedLevel { from, to } => write!(
+                f,
+                "the <h{}> {:?} skips a level after the <h{}> {:?}",
+                to.0, to.1, from.0, from.1
+            ),
+            HeadingProblem::TitleCount(level, count) => {
+                write!(f, "expected exactly one <h{}>, found {}", level, count)
+            }
+        }
+    }
+}
+
+/// Checks that the headings of the rendered html descend one level at a time, and that
+/// there's exactly one at `title_level` (1 for `<h1>`, or lower when headings are offset).
+pub fn check_heading_hierarchy(html: &str, title_level: usize) -> Vec<HeadingProblem> {
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+
+    let mut problems = Vec::new();
+    let mut titles = 0;
+    let mut previous: Option<(usize, String)> = None;
+    for heading in fragment.select(&selector) {
+        let level = heading.value().name()[1..].parse::<usize>().unwrap_or(6);
+        let text = heading.text().collect::<String>().trim().to_string();
+        if level == title_level {
+            titles += 1;
+        }
+        if let Some(from) = previous.take() {
+            if level > from.0 + 1 {
+                problems.push(HeadingProblem::SkippedLevel {
+                    from,
+                    to: (level, text.clone()),
+                });
+            }
+        }
+        previous = Some((level, text));
+    }
+
+    if titles != 1 {
+        problems.push(HeadingProblem::TitleCount(title_level, titles));
+    }
+
+    problems
+}
+
+/// Finds the markdown that only renders as intended with an extension to CommonMark (a
+/// table, footnote, strikethrough, task list item, heading attributes or smart
+/// punctuation), which other CommonMark renderers would show differently. Each is given
+/// with the byte offset of where it starts in the markdown.
+pub fn find_extension_constructs(md_content: &str) -> Vec<(usize, &'static str)> {
+    let mut constructs: Vec<(usize, &'static str)> = Vec::new();
+    for (event, range) in Parser::new_ext(md_content, Options::all()).into_offset_iter() {
+        let construct = match event {
+            Event::Start(Tag::Table(_)) => "a table",
+            Event::Start(Tag::FootnoteDefinition(_)) | Event::FootnoteReference(_) => "a footnote",
+            Event::Start(Tag::Strikethrough) => "strikethrough",
+            Event::TaskListMarker(_) => "a task list item",
+            Event::Start(Tag::Heading(_, id, classes)) if id.is_some() || !classes.is_empty() => {
+                "heading attributes"
+            }
+            // text that isn't in the markdown as it is, other than an entity or an escaped
+            // character, can only have come from smart punctuation
+            Event::Text(text) => {
+                let source = &md_content[range.clone()];
+                if *text == *source || source.starts_with(['&', '\\']) {
+                    continue;
+                }
+                "smart punctuation"
+            }
+            _ => continue,
+        };
+        constructs.push((range.start, construct));
+    }
+
+    constructs
+}
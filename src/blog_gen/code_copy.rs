@@ -0,0 +1,46 @@
+use regex::Regex;
+
+/// Wraps each `<pre>` block in a `<div class="code-block">` along with a button for
+/// copying its contents, returning `None` when there are no code blocks at all.
+pub fn add_copy_buttons(html: &str) -> Option<String> {
+    let pre_regex = Regex::new(r"(?i)<pre[\s>]").unwrap();
+    if !pre_regex.is_match(html) {
+        return None;
+    }
+
+    let html = pre_regex.replace_all(html, |caps: &regex::Captures| {
+        format!(
+            "<div class=\"code-block\"><button class=\"copy-code\" type=\"button\">Copy</button>{}",
+            &caps[0]
+        )
+    });
+
+    let pre_end_regex = Regex::new(r"(?i)</pre\s*>").unwrap();
+    Some(pre_end_regex.replace_all(&html, "$0</div>").into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_blocks_get_a_copy_button() {
+        assert_eq!(
+            add_copy_buttons("<p>x</p>\n<pre><code>a\n</code></pre>\n").unwrap(),
+            "<p>x</p>\n<div class=\"code-block\"><button class=\"copy-code\" type=\"button\">Copy</button>\
+             <pre><code>a\n</code></pre></div>\n"
+        );
+    }
+
+    #[test]
+    fn uppercase_tags_are_closed_too() {
+        let html = add_copy_buttons("<PRE class=\"x\">a</PRE >").unwrap();
+        assert!(html.ends_with("<PRE class=\"x\">a</PRE ></div>"));
+        assert_eq!(html.matches("<div").count(), html.matches("</div>").count());
+    }
+
+    #[test]
+    fn posts_without_code_blocks_are_left_alone() {
+        assert_eq!(add_copy_buttons("<p>preformatted</p>"), None);
+    }
+}
@@ -0,0 +1,60 @@
+/// The config file read from the current dir when no other is given, as written by
+/// `--init`.
+pub const CONFIG_FILE: &str = "md-blog-gen.conf";
+
+/// Parses a config file of command line options, one per line as `name = value` (or just
+/// `name` for a switch), with `#` starting a comment line:
+///
+/// ```text
+/// # the paths are relative to the dir the generator is run from
+/// css-source = css_sources/style.css
+/// md-sources = md_sources
+/// toc
+/// ```
+///
+/// The names are the long option names, without the leading `--`. Values are taken as
+/// they're written, up to the end of the line, so they need no quoting.
+pub fn parse_config(contents: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut options = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().to_string())),
+            None => (line, None),
+        };
+        let name = name.strip_prefix("--").unwrap_or(name);
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("line {}: {:?} isn't an option name", i + 1, name));
+        }
+        options.push((name.to_string(), value));
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_and_switches_are_read() {
+        let options = parse_config("# a comment\n\ncss-source = a b.css\n--toc\n").unwrap();
+        assert_eq!(
+            options,
+            vec![
+                ("css-source".to_string(), Some("a b.css".to_string())),
+                ("toc".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_that_arent_options_are_errors() {
+        let error = parse_config("toc\nnot an option\n").unwrap_err();
+        assert!(error.starts_with("line 2:"));
+    }
+}
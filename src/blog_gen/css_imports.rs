@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::{Captures, Regex};
+
+use super::blog_generator::BlogGeneratorError;
+
+/// Reads the CSS file at `css_path`, replacing every local `@import` statement with
+/// the contents of the imported file (resolved relative to the importing file).
+///
+/// Both `@import "file.css";` and `@import url("file.css");` forms are handled, and an
+/// import carrying a media query is wrapped in a matching `@media` block.
+/// Remote imports (`http://`, `https://` and protocol-relative `//`) are kept as imports, but
+/// are hoisted to the top of the result since browsers ignore an `@import` following other rules.
+/// A remote import hoisted out of a local import with a media query takes the media query
+/// along with it.
+pub fn resolve_css_imports(css_path: &Path) -> Result<String, BlogGeneratorError> {
+    let mut import_stack: Vec<PathBuf> = Vec::new();
+    let resolved = resolve_imports_recursive(css_path, &mut import_stack)?;
+
+    let mut remote_imports: Vec<String> = Vec::new();
+    let remaining = import_regex().replace_all(&resolved, |caps: &Captures| {
+        remote_imports.push(caps[0].to_string());
+        ""
+    });
+
+    if remote_imports.is_empty() {
+        return Ok(resolved);
+    }
+
+    Ok(format!("{}\n{}", remote_imports.join("\n"), remaining))
+}
+
+fn import_regex() -> Regex {
+    Regex::new(r#"@import\s+(?:url\(\s*["']?([^"')]+?)["']?\s*\)|["']([^"']+)["'])\s*([^;]*);"#)
+        .unwrap()
+}
+
+fn resolve_imports_recursive(
+    css_path: &Path,
+    import_stack: &mut Vec<PathBuf>,
+) -> Result<String, BlogGeneratorError> {
+    let canonical_path = fs::canonicalize(css_path).map_err(|e| {
+        BlogGeneratorError::InvalidCSSPath(format!("{}", css_path.display()), e.to_string())
+    })?;
+
+    if import_stack.contains(&canonical_path) {
+        let cycle: Vec<String> = import_stack
+            .iter()
+            .chain(std::iter::once(&canonical_path))
+            .map(|p| format!("{}", p.display()))
+            .collect();
+        return Err(BlogGeneratorError::CSSImportCycle(
+            format!("{}", css_path.display()),
+            cycle.join(" -> "),
+        ));
+    }
+
+    let css = fs::read_to_string(&canonical_path).map_err(|e| {
+        BlogGeneratorError::CSSSourceError(format!("{}", css_path.display()), e.to_string())
+    })?;
+
+    let base_dir = canonical_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    import_stack.push(canonical_path);
+
+    let mut error: Option<BlogGeneratorError> = None;
+    let resolved = import_regex().replace_all(&css, |caps: &Captures| {
+        let import_target = caps.get(1).or(caps.get(2)).unwrap().as_str().trim();
+        let media_query = caps.get(3).map(|m| m.as_str().trim()).unwrap_or("");
+
+        if error.is_some() || is_remote_import(import_target) {
+            return caps[0].to_string();
+        }
+
+        match resolve_imports_recursive(&base_dir.join(import_target), import_stack) {
+            Ok(imported) if media_query.is_empty() => imported,
+            Ok(imported) => format!(
+                "@media {} {{\n{}\n}}",
+                media_query,
+                with_remote_media(&imported, media_query)
+            ),
+            Err(e) => {
+                error = Some(e);
+                caps[0].to_string()
+            }
+        }
+    });
+
+    import_stack.pop();
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(resolved.into_owned()),
+    }
+}
+
+/// The css with its remote imports limited to the `media_query` as well as their own, since
+/// they'll be hoisted out of the `@media` block the css goes in.
+fn with_remote_media(css: &str, media_query: &str) -> String {
+    import_regex()
+        .replace_all(css, |caps: &Captures| {
+            let import_target = caps.get(1).or(caps.get(2)).unwrap().as_str().trim();
+            if !is_remote_import(import_target) {
+                return caps[0].to_string();
+            }
+
+            let own_media = caps.get(3).map(|m| m.as_str().trim()).unwrap_or("");
+            format!(
+                "@import url(\"{}\") {};",
+                import_target,
+                combined_media(media_query, own_media)
+            )
+        })
+        .into_owned()
+}
+
+/// The media query list matching where both `outer` and `inner` do. A media type can't
+/// be combined with another, so the pairs of different types are left out (leaving
+/// `not all` when nothing is left).
+fn combined_media(outer: &str, inner: &str) -> String {
+    if inner.is_empty() {
+        return outer.to_string();
+    }
+
+    let mut combined = Vec::new();
+    for outer in outer.split(',').map(str::trim) {
+        for inner in inner.split(',').map(str::trim) {
+            if outer == inner {
+                combined.push(outer.to_string());
+            } else if inner.starts_with('(') {
+                combined.push(format!("{} and {}", outer, inner));
+            } else if outer.starts_with('(') {
+                combined.push(format!("{} and {}", inner, outer));
+            }
+        }
+    }
+
+    if combined.is_empty() {
+        return "not all".to_string();
+    }
+    combined.join(", ")
+}
+
+fn is_remote_import(import_target: &str) -> bool {
+    import_target.starts_with("http://")
+        || import_target.starts_with("https://")
+        || import_target.starts_with("//")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn css_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("md-blog-gen-css-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file_name, css) in files {
+            fs::write(dir.join(file_name), css).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn local_imports_are_inlined() {
+        let dir = css_dir(
+            "local",
+            &[
+                (
+                    "main.css",
+                    "@import \"a.css\";\n@import url(b.css) print;\nbody {}\n",
+                ),
+                ("a.css", "a {}"),
+                ("b.css", "b {}"),
+            ],
+        );
+        assert_eq!(
+            resolve_css_imports(&dir.join("main.css")).unwrap(),
+            "a {}\n@media print {\nb {}\n}\nbody {}\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remote_imports_keep_the_media_query_of_the_import_they_come_from() {
+        let dir = css_dir(
+            "remote",
+            &[
+                ("main.css", "@import \"fonts.css\" screen;\nbody {}\n"),
+                (
+                    "fonts.css",
+                    "@import url(\"https://example.com/a.css\");\n\
+                     @import \"https://example.com/b.css\" (min-width: 600px);\nh1 {}\n",
+                ),
+            ],
+        );
+        let css = resolve_css_imports(&dir.join("main.css")).unwrap();
+        assert!(css.starts_with(
+            "@import url(\"https://example.com/a.css\") screen;\n\
+             @import url(\"https://example.com/b.css\") screen and (min-width: 600px);\n"
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn media_queries_are_combined() {
+        assert_eq!(combined_media("screen", ""), "screen");
+        assert_eq!(
+            combined_media("screen, print", "(color)"),
+            "screen and (color), print and (color)"
+        );
+        assert_eq!(
+            combined_media("(min-width: 1px)", "print"),
+            "print and (min-width: 1px)"
+        );
+        assert_eq!(combined_media("screen", "screen"), "screen");
+        assert_eq!(combined_media("screen", "print"), "not all");
+    }
+
+    #[test]
+    fn import_cycles_are_errors() {
+        let dir = css_dir(
+            "cycle",
+            &[
+                ("a.css", "@import \"b.css\";"),
+                ("b.css", "@import \"a.css\";"),
+            ],
+        );
+        assert!(matches!(
+            resolve_css_imports(&dir.join("a.css")),
+            Err(BlogGeneratorError::CSSImportCycle(_, _))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
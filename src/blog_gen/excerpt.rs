@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser};
+use scraper::{Html, Selector};
+
+/// An html comment marking the end of a post's excerpt in its markdown source.
+pub const EXCERPT_MARKER: &str = "<!-- more -->";
+
+/// Where the excerpt markers are in the markdown source. Only the markers that are html
+/// comments to the markdown parser count, not the ones in code blocks or code spans.
+fn marker_ranges(md_content: &str) -> Vec<Range<usize>> {
+    Parser::new(md_content)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Html(html) if html.contains(EXCERPT_MARKER)))
+        .filter_map(|(_, range)| {
+            let start = range.start + md_content[range].find(EXCERPT_MARKER)?;
+            Some(start..start + EXCERPT_MARKER.len())
+        })
+        .collect()
+}
+
+/// Returns the markdown above the excerpt marker, if the source contains one.
+pub fn markdown_above_marker(md_content: &str) -> Option<&str> {
+    marker_ranges(md_content)
+        .first()
+        .map(|marker| &md_content[..marker.start])
+}
+
+/// Removes the excerpt marker so that it doesn't end up in the full rendered post.
+pub fn strip_excerpt_marker(md_content: &str) -> String {
+    let mut stripped = md_content.to_string();
+    for marker in marker_ranges(md_content).into_iter().rev() {
+        stripped.replace_range(marker, "");
+    }
+
+    stripped
+}
+
+/// Returns the first paragraph of the rendered html, for posts without an excerpt marker.
+pub fn first_paragraph(html: &str) -> Option<String> {
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("p").unwrap();
+
+    fragment.select(&selector).next().map(|p| p.html())
+}
+
+/// Removes the first `heading` element (the post title) from the rendered html,
+/// since the index already shows the title next to the excerpt.
+pub fn without_title_heading(html: &str, heading: &str) -> String {
+    let mut fragment = Html::parse_fragment(html);
+    let selector = Selector::parse(heading).unwrap();
+
+    let title_id = fragment.select(&selector).next().map(|h| h.id());
+    if let Some(mut title) = title_id.and_then(|id| fragment.tree.get_mut(id)) {
+        title.detach();
+    }
+
+    fragment.root_element().inner_html().trim().to_string()
+}
+
+/// Whether the post has more to it than its `excerpt`, going by their text: the excerpt
+/// is compared against the post's rendered html with its title heading taken out.
+pub fn is_truncated(excerpt: &str, html: &str, heading: &str) -> bool {
+    let text_len = |html: &str| {
+        Html::parse_fragment(html)
+            .root_element()
+            .text()
+            .collect::<String>()
+            .split_whitespace()
+            .map(str::len)
+            .sum::<usize>()
+    };
+
+    text_len(excerpt) < text_len(&without_title_heading(html, heading))
+}
+
+/// The title shortened to `max_chars` characters (not bytes), ending in an ellipsis, or
+/// `None` if it's short enough already.
+pub fn truncate_title(title: &str, max_chars: usize) -> Option<String> {
+    if title.chars().count() <= max_chars {
+        return None;
+    }
+
+    let kept: String = title.chars().take(max_chars.saturating_sub(1)).collect();
+    Some(format!("{}\u{2026}", kept.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_excerpt_ends_at_the_marker() {
+        let md_content = "# Title\n\nexcerpt\n\n<!-- more -->\n\nthe rest\n";
+        assert_eq!(
+            markdown_above_marker(md_content),
+            Some("# Title\n\nexcerpt\n\n")
+        );
+        assert_eq!(
+            strip_excerpt_marker(md_content),
+            "# Title\n\nexcerpt\n\n\n\nthe rest\n"
+        );
+    }
+
+    #[test]
+    fn markers_in_code_are_left_alone() {
+        let md_content = "# Title\n\n```html\n<!-- more -->\n```\n\nuse `<!-- more -->`\n";
+        assert_eq!(markdown_above_marker(md_content), None);
+        assert_eq!(strip_excerpt_marker(md_content), md_content);
+
+        let md_content = format!("{}\n<!-- more -->\n\nthe rest\n", md_content);
+        assert_eq!(
+            markdown_above_marker(&md_content).map(|above| above.contains("```html")),
+            Some(true)
+        );
+        assert!(strip_excerpt_marker(&md_content).contains("```html\n<!-- more -->\n```"));
+    }
+
+    #[test]
+    fn titles_are_truncated_by_characters() {
+        assert_eq!(truncate_title("short", 10), None);
+        assert_eq!(
+            truncate_title("Ünïcödé títlé", 6).as_deref(),
+            Some("Ünïcö\u{2026}")
+        );
+    }
+
+    #[test]
+    fn titles_are_never_cut_inside_a_character() {
+        // each of these takes several bytes
+        assert_eq!(
+            truncate_title("日本語のタイトル", 4).as_deref(),
+            Some("日本語\u{2026}")
+        );
+        assert_eq!(
+            truncate_title("🦀🦀🦀🦀", 3).as_deref(),
+            Some("🦀🦀\u{2026}")
+        );
+        assert_eq!(truncate_title("🦀🦀🦀", 3), None);
+        // nor left with a space before the ellipsis
+        assert_eq!(truncate_title("ab cd", 4).as_deref(), Some("ab\u{2026}"));
+    }
+}
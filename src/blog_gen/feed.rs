@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::Serialize;
+
+/// The syndication feeds that can be generated alongside the index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum FeedFormat {
+    /// `feed.xml`, in RSS 2.0
+    Rss,
+
+    /// `atom.xml`
+    Atom,
+
+    /// both of the above
+    Both,
+
+    /// `feed.json`, in JSON Feed 1.1
+    Json,
+
+    /// `feed.xml`, `atom.xml` and `feed.json`
+    All,
+}
+
+impl FeedFormat {
+    pub fn rss(&self) -> bool {
+        matches!(self, FeedFormat::Rss | FeedFormat::Both | FeedFormat::All)
+    }
+
+    pub fn atom(&self) -> bool {
+        matches!(self, FeedFormat::Atom | FeedFormat::Both | FeedFormat::All)
+    }
+
+    pub fn json(&self) -> bool {
+        matches!(self, FeedFormat::Json | FeedFormat::All)
+    }
+}
+
+/// How much of each post goes into the feeds, besides its title and link.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FeedContent {
+    /// the whole rendered post, so that it can be read in the feed reader
+    Full,
+
+    /// the post's excerpt
+    #[default]
+    Excerpt,
+
+    /// nothing but the title and link
+    None,
+}
+
+/// A post as it appears in a feed.
+#[derive(Clone, Debug)]
+pub struct FeedEntry {
+    pub title: String,
+    pub url: String,
+    pub published: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+
+    /// the html of the post's excerpt, left out of the feed when empty
+    pub summary: String,
+
+    /// the html of the whole post, left out of the feed when empty
+    pub content: String,
+}
+
+/// The details of the site the feeds are for.
+#[derive(Clone, Debug)]
+pub struct FeedChannel {
+    pub title: String,
+    pub home_url: String,
+
+    /// the url of the feed itself
+    pub feed_url: String,
+}
+
+/// Orders feed entries newest first, the way feed readers expect them, breaking ties by
+/// url so that the same posts always give the same feed.
+pub fn sort_entries(entries: &mut [FeedEntry]) {
+    entries.sort_by(|a, b| {
+        b.published
+            .cmp(&a.published)
+            .then_with(|| a.url.cmp(&b.url))
+    });
+}
+
+/// Renders an RSS 2.0 feed, using the publish date of each entry as its `pubDate`.
+pub fn rss_feed(channel: &FeedChannel, entries: &[FeedEntry]) -> String {
+    let mut rss = String::new();
+    rss.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    rss.push_str(
+        "<rss version=\"2.0\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n",
+    );
+    rss.push_str("<channel>\n");
+    rss.push_str(&format!("<title>{}</title>\n", encode_text(&channel.title)));
+    rss.push_str(&format!(
+        "<link>{}</link>\n",
+        encode_text(&channel.home_url)
+    ));
+    rss.push_str(&format!(
+        "<description>{}</description>\n",
+        encode_text(&channel.title)
+    ));
+
+    for entry in entries {
+        rss.push_str("<item>\n");
+        rss.push_str(&format!("<title>{}</title>\n", encode_text(&entry.title)));
+        rss.push_str(&format!("<link>{}</link>\n", encode_text(&entry.url)));
+        rss.push_str(&format!("<guid>{}</guid>\n", encode_text(&entry.url)));
+        rss.push_str(&format!(
+            "<pubDate>{}</pubDate>\n",
+            entry.published.to_rfc2822()
+        ));
+        if !entry.summary.is_empty() {
+            rss.push_str(&format!(
+                "<description>{}</description>\n",
+                encode_text(&entry.summary)
+            ));
+        }
+        if !entry.content.is_empty() {
+            rss.push_str(&format!(
+                "<content:encoded>{}</content:encoded>\n",
+                cdata(&entry.content)
+            ));
+        }
+        rss.push_str("</item>\n");
+    }
+
+    rss.push_str("</channel>\n</rss>\n");
+    rss
+}
+
+/// Renders an Atom feed, with both the publish and last updated dates of each entry.
+pub fn atom_feed(channel: &FeedChannel, entries: &[FeedEntry]) -> String {
+    let updated = entries
+        .iter()
+        .map(|entry| entry.updated)
+        .max()
+        .unwrap_or_default();
+
+    let mut atom = String::new();
+    atom.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    atom.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    atom.push_str(&format!("<title>{}</title>\n", encode_text(&channel.title)));
+    atom.push_str(&format!("<id>{}</id>\n", encode_text(&channel.home_url)));
+    atom.push_str(&format!(
+        "<link href=\"{}\"/>\n",
+        encode_double_quoted_attribute(&channel.home_url)
+    ));
+    atom.push_str(&format!(
+        "<link rel=\"self\" href=\"{}\"/>\n",
+        encode_double_quoted_attribute(&channel.feed_url)
+    ));
+    atom.push_str(&format!("<updated>{}</updated>\n", updated.to_rfc3339()));
+    // atom requires an author, and the site is the best there is to go on
+    atom.push_str(&format!(
+        "<author><name>{}</name></author>\n",
+        encode_text(&channel.title)
+    ));
+
+    for entry in entries {
+        atom.push_str("<entry>\n");
+        atom.push_str(&format!("<title>{}</title>\n", encode_text(&entry.title)));
+        atom.push_str(&format!(
+            "<link href=\"{}\"/>\n",
+            encode_double_quoted_attribute(&entry.url)
+        ));
+        atom.push_str(&format!("<id>{}</id>\n", encode_text(&entry.url)));
+        atom.push_str(&format!(
+            "<published>{}</published>\n",
+            entry.published.to_rfc3339()
+        ));
+        atom.push_str(&format!(
+            "<updated>{}</updated>\n",
+            entry.updated.to_rfc3339()
+        ));
+        if !entry.summary.is_empty() {
+            atom.push_str(&format!(
+                "<summary type=\"html\">{}</summary>\n",
+                encode_text(&entry.summary)
+            ));
+        }
+        if !entry.content.is_empty() {
+            atom.push_str(&format!(
+                "<content type=\"html\">{}</content>\n",
+                cdata(&entry.content)
+            ));
+        }
+        atom.push_str("</entry>\n");
+    }
+
+    atom.push_str("</feed>\n");
+    atom
+}
+
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: &'a str,
+    items: Vec<JsonFeedItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem<'a> {
+    id: &'a str,
+    url: &'a str,
+    title: &'a str,
+    content_html: &'a str,
+    date_published: String,
+    date_modified: String,
+}
+
+/// Renders a JSON Feed 1.1. Every item needs some content, so an entry without any has
+/// its excerpt (or nothing at all) as its `content_html`.
+pub fn json_feed(channel: &FeedChannel, entries: &[FeedEntry]) -> String {
+    let feed = JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: &channel.title,
+        home_page_url: &channel.home_url,
+        feed_url: &channel.feed_url,
+        items: entries
+            .iter()
+            .map(|entry| JsonFeedItem {
+                id: &entry.url,
+                url: &entry.url,
+                title: &entry.title,
+                content_html: if entry.content.is_empty() {
+                    &entry.summary
+                } else {
+                    &entry.content
+                },
+                date_published: entry.published.to_rfc3339(),
+                date_modified: entry.updated.to_rfc3339(),
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&feed).unwrap()
+}
+
+/// Wraps html in a CDATA section, splitting any `]]>` in it across two sections since it
+/// would otherwise end the first one early.
+fn cdata(html: &str) -> String {
+    format!("<![CDATA[{}]]>", html.replace("]]>", "]]]]><![CDATA[>"))
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use regex::{Captures, Regex};
+
+/// Turns each image that starts a paragraph of its own, followed by caption text, into a
+/// numbered `<figure>`:
+///
+/// ```text
+/// ![A plot](plot.png)
+/// Response times over the week {#fig:latency}
+/// ```
+///
+/// becomes a figure captioned "Figure 1: Response times over the week". A `{#fig:label}`
+/// at the end of the caption gives the figure an id (`fig-label`), and `{{fig:label}}`
+/// anywhere in the post (but in code) is replaced with a link to it ("Figure 1"). Returns
+/// the html along with the references to labels that don't exist, which are left as they
+/// are.
+pub fn number_figures(html: &str) -> (String, Vec<String>) {
+    let figure = Regex::new(r"(?s)<p>(<img\b[^>]*>)\n(.+?)</p>").unwrap();
+    let label = Regex::new(r"\s*\{#fig:([\w-]+)\}\s*$").unwrap();
+
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    let mut count = 0;
+    let html = figure.replace_all(html, |caps: &Captures| {
+        let caption = caps[2].trim();
+        if caption.is_empty() {
+            return caps[0].to_string();
+        }
+
+        count += 1;
+        let (caption, id) = match label.captures(caption) {
+            Some(label_caps) => {
+                numbers.insert(label_caps[1].to_string(), count);
+                let id = format!(" id=\"fig-{}\"", &label_caps[1]);
+                (
+                    caption[..label_caps.get(0).unwrap().start()].to_string(),
+                    id,
+                )
+            }
+            None => (caption.to_string(), String::new()),
+        };
+
+        format!(
+            "<figure{}>{}<figcaption>Figure {}: {}</figcaption></figure>",
+            id, &caps[1], count, caption
+        )
+    });
+
+    let reference = Regex::new(r"\{\{fig:([\w-]+)\}\}").unwrap();
+    let code = Regex::new(r"(?is)<pre\b.*?</pre\s*>|<code\b.*?</code\s*>").unwrap();
+    let mut unresolved: Vec<String> = Vec::new();
+    let mut resolve = |text: &str| {
+        reference
+            .replace_all(text, |caps: &Captures| match numbers.get(&caps[1]) {
+                Some(number) => format!("<a href=\"#fig-{}\">Figure {}</a>", &caps[1], number),
+                None => {
+                    unresolved.push(caps[0].to_string());
+                    caps[0].to_string()
+                }
+            })
+            .into_owned()
+    };
+
+    // the references in code blocks and code spans are left as they're written
+    let mut resolved = String::new();
+    let mut rest = 0;
+    for code in code.find_iter(&html) {
+        resolved.push_str(&resolve(&html[rest..code.start()]));
+        resolved.push_str(code.as_str());
+        rest = code.end();
+    }
+    resolved.push_str(&resolve(&html[rest..]));
+
+    (resolved, unresolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captioned_images_become_numbered_figures() {
+        let (html, unresolved) = number_figures(
+            "<p><img src=\"a.png\" alt=\"a\">\nThe plot {#fig:plot}</p>\n<p>See {{fig:plot}}.</p>\n",
+        );
+        assert_eq!(
+            html,
+            "<figure id=\"fig-plot\"><img src=\"a.png\" alt=\"a\"><figcaption>Figure 1: The plot</figcaption></figure>\n\
+             <p>See <a href=\"#fig-plot\">Figure 1</a>.</p>\n"
+        );
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn references_in_code_are_left_alone() {
+        let (html, unresolved) = number_figures(
+            "<p><img src=\"a.png\">\nThe plot {#fig:plot}</p>\n\
+             <pre><code>{{fig:plot}}\n</code></pre>\n<p>Write <code>{{fig:other}}</code>.</p>\n",
+        );
+        assert!(html.contains("<pre><code>{{fig:plot}}\n</code></pre>"));
+        assert!(html.contains("<code>{{fig:other}}</code>"));
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn unknown_references_are_reported() {
+        let (html, unresolved) = number_figures("<p>See {{fig:missing}}.</p>");
+        assert_eq!(html, "<p>See {{fig:missing}}.</p>");
+        assert_eq!(unresolved, vec!["{{fig:missing}}"]);
+    }
+}
@@ -0,0 +1,20 @@
+use std::io;
+use std::path::Path;
+
+/// Sets the Unix permission bits (like `0o644`) of a generated file. There are no such
+/// permissions elsewhere, so on other platforms this does nothing.
+pub fn set_file_mode(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+}
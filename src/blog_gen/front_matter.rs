@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+
+/// Metadata declared in a YAML block at the top of a markdown source file, delimited by
+/// `---` lines:
+///
+/// ```text
+/// ---
+/// series: Building a blog generator
+/// ---
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FrontMatter {
+    /// posts sharing a series are linked together and listed on a series page
+    pub series: Option<String>,
+
+    /// posts are listed on a page for each of their tags
+    pub tags: Vec<String>,
+
+    /// the language of the post, set on its `<html lang>`; each language gets an
+    /// `index.<lang>.html` listing only its posts
+    pub lang: Option<String>,
+
+    /// a video shown above the post, copied to the output along with its `hero_poster` image
+    pub hero_video: Option<String>,
+    pub hero_poster: Option<String>,
+
+    /// the url the post was first published at, for its `<link rel="canonical">`
+    pub canonical: Option<String>,
+
+    /// old paths of the post (`old-slug.html`), each of which gets a page redirecting to it
+    pub aliases: Vec<String>,
+
+    /// where the post goes when ordering the posts by `order` rather than by date
+    pub order: Option<i64>,
+
+    /// drafts are left out of the build unless drafts are asked for
+    pub draft: bool,
+
+    /// the text of headings to leave out of the table of contents (a heading can also be
+    /// left out with a `{.no-toc}` class, when heading attributes are enabled)
+    pub toc_exclude: Vec<String>,
+
+    /// set to false to turn off smart punctuation (curly quotes, dashes) for this post
+    pub typography: Option<bool>,
+
+    /// set to true to keep the raw html of this post, even when raw html is disabled
+    pub allow_html: bool,
+
+    /// set to false to leave this post's headings unnumbered when numbering headings (or
+    /// to true to number them when not)
+    pub number_headings: Option<bool>,
+
+    /// any other keys, passed through to the templates as `meta`
+    #[serde(flatten)]
+    pub meta: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl FrontMatter {
+    /// The value of a string key in `meta`, if there is one.
+    pub fn meta_str(&self, key: &str) -> Option<&str> {
+        self.meta.get(key).and_then(|value| value.as_str())
+    }
+
+    /// The post's `date`, either a plain `YYYY-MM-DD` date (taken as midnight UTC)
+    /// or an RFC 3339 timestamp. It takes the place of the file's creation time, and
+    /// the key is left in `meta` for the templates as well.
+    pub fn date(&self) -> Result<Option<DateTime<Utc>>, String> {
+        self.date_value("date")
+    }
+
+    /// When the post was last `updated`, in the same formats as `date`.
+    pub fn updated(&self) -> Result<Option<DateTime<Utc>>, String> {
+        self.date_value("updated")
+    }
+
+    fn date_value(&self, key: &str) -> Result<Option<DateTime<Utc>>, String> {
+        let Some(value) = self.meta.get(key) else {
+            return Ok(None);
+        };
+        let Some(date) = value.as_str() else {
+            return Err(format!("expected a date string, got {:?}", value));
+        };
+
+        if let Ok(date_time) = DateTime::parse_from_rfc3339(date) {
+            return Ok(Some(date_time.with_timezone(&Utc)));
+        }
+
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| Some(d.and_hms_opt(0, 0, 0).unwrap().and_utc()))
+            .map_err(|e| {
+                format!(
+                    "{:?} is not a YYYY-MM-DD date or RFC 3339 timestamp: {}",
+                    date, e
+                )
+            })
+    }
+}
+
+/// Splits the front matter YAML (if any) off the start of the markdown source, returning
+/// it along with the remaining markdown.
+pub fn split_front_matter(md_content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = md_content.strip_prefix("---\n") else {
+        return (None, md_content);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    // no closing delimiter, so this wasn't front matter after all
+    (None, md_content)
+}
+
+/// Splits the metadata in the HTML comments (if any) off the start of the markdown source,
+/// returning the `key: value` pairs they declare along with the remaining markdown. This
+/// is how some older posts carry their metadata, in place of front matter:
+///
+/// ```text
+/// <!-- title: Hello World -->
+/// <!--
+/// date: 2019-03-02
+/// tags: [intro, meta]
+/// -->
+/// ```
+///
+/// A comment with anything other than `key: value` lines in it ends the metadata, and is
+/// left in the markdown.
+pub fn split_comment_metadata(md_content: &str) -> (Vec<(String, String)>, &str) {
+    let mut metadata = Vec::new();
+    let mut rest = md_content;
+
+    while let Some(comment) = rest.trim_start().strip_prefix("<!--") {
+        let Some((comment, after)) = comment.split_once("-->") else {
+            break;
+        };
+        let pairs: Option<Vec<(String, String)>> = comment
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (key, value) = line.split_once(':')?;
+                let key = key.trim();
+                let is_key = !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                is_key.then(|| (key.to_string(), value.trim().to_string()))
+            })
+            .collect();
+        match pairs {
+            Some(pairs) if !pairs.is_empty() => metadata.extend(pairs),
+            _ => break,
+        }
+        rest = after;
+    }
+
+    if metadata.is_empty() {
+        return (metadata, md_content);
+    }
+    // drop what's left of the line ending the last comment, and the blank lines after it
+    while let Some((line, after)) = rest.split_once('\n') {
+        if !line.trim().is_empty() {
+            break;
+        }
+        rest = after;
+    }
+
+    (metadata, rest)
+}
+
+pub fn parse_front_matter(yaml: &str) -> Result<FrontMatter, serde_yaml::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(FrontMatter::default());
+    }
+
+    serde_yaml::from_str(yaml)
+}
+
+/// Parses the front matter, taking the keys it doesn't set from the `metadata` pairs
+/// (from `split_comment_metadata`). Lists and mappings (`[intro, meta]`), booleans and
+/// numbers are read as YAML, so that they work in comments too; anything else is taken
+/// as a string, as it's written (`title: Rust: a retrospective`).
+pub fn parse_front_matter_with(
+    yaml: &str,
+    metadata: &[(String, String)],
+) -> Result<FrontMatter, serde_yaml::Error> {
+    if metadata.is_empty() {
+        return parse_front_matter(yaml);
+    }
+
+    let mut mapping = match yaml.trim() {
+        "" => Mapping::new(),
+        yaml => serde_yaml::from_str(yaml)?,
+    };
+    for (key, value) in metadata {
+        let key = Value::String(key.clone());
+        if !mapping.contains_key(&key) {
+            mapping.insert(key, comment_value(value));
+        }
+    }
+
+    serde_yaml::from_value(Value::Mapping(mapping))
+}
+
+/// The YAML value of a comment metadata `value`.
+fn comment_value(value: &str) -> Value {
+    let string = || Value::String(value.to_string());
+    let is_yaml = value.starts_with('[')
+        || value.starts_with('{')
+        || value == "true"
+        || value == "false"
+        || value.parse::<f64>().is_ok();
+    if !is_yaml {
+        return string();
+    }
+
+    serde_yaml::from_str(value).unwrap_or_else(|_| string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn front_matter_of(md_content: &str) -> FrontMatter {
+        let (metadata, _) = split_comment_metadata(md_content);
+        parse_front_matter_with("", &metadata).unwrap()
+    }
+
+    #[test]
+    fn comment_metadata_sets_the_title() {
+        let front_matter = front_matter_of("<!-- title: Rust: a retrospective -->\n# Rust\n");
+        assert_eq!(
+            front_matter.meta_str("title"),
+            Some("Rust: a retrospective")
+        );
+    }
+
+    #[test]
+    fn comment_metadata_reads_lists_and_booleans() {
+        let front_matter =
+            front_matter_of("<!--\ntags: [intro, meta]\ndraft: true\norder: 3\n-->\n# Hi\n");
+        assert_eq!(front_matter.tags, vec!["intro", "meta"]);
+        assert!(front_matter.draft);
+        assert_eq!(front_matter.order, Some(3));
+    }
+
+    #[test]
+    fn front_matter_takes_precedence_over_comments() {
+        let (metadata, _) = split_comment_metadata("<!-- series: Old -->\n# Hi\n");
+        let front_matter = parse_front_matter_with("series: New", &metadata).unwrap();
+        assert_eq!(front_matter.series.as_deref(), Some("New"));
+    }
+}
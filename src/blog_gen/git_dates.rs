@@ -0,0 +1,45 @@
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{DateTime, Utc};
+
+/// The dates of the first and last commits touching a file.
+#[derive(Clone, Copy, Debug)]
+pub struct GitDates {
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+}
+
+/// Reads the commit history of the file at `path` using the `git` command line tool.
+/// Returns `None` if git isn't available, the file isn't in a git repository, or the file
+/// hasn't been committed yet.
+pub fn git_dates(path: &Path) -> Option<GitDates> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let file_name = path.file_name()?;
+
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(["log", "--follow", "--format=%cI", "--"])
+        .arg(file_name)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // the log is newest first
+    let log = String::from_utf8_lossy(&output.stdout);
+    let mut dates = log
+        .lines()
+        .filter_map(|line| DateTime::parse_from_rfc3339(line.trim()).ok())
+        .map(|date| date.with_timezone(&Utc));
+
+    let modified = dates.next()?;
+    let created = dates.next_back().unwrap_or(modified);
+
+    Some(GitDates { created, modified })
+}
@@ -0,0 +1,104 @@
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag};
+
+/// The section number (`1`, `1.1`, `1.2`, `2`, ...) of each of the headings at `levels`,
+/// or `None` for those at or above `title_level`, which aren't numbered (and start the
+/// numbering over). A skipped level (an `<h2>` followed directly by an `<h4>`) only goes
+/// one number deeper, so that no numbers are left out.
+pub fn heading_numbers(levels: &[HeadingLevel], title_level: HeadingLevel) -> Vec<Option<String>> {
+    let mut sections: Vec<(HeadingLevel, usize)> = Vec::new();
+
+    levels
+        .iter()
+        .map(|&level| {
+            if level <= title_level {
+                sections.clear();
+                return None;
+            }
+
+            // a heading between two levels carries on from the deeper one it closes
+            let mut closed = None;
+            while sections.last().is_some_and(|(open, _)| *open > level) {
+                closed = sections.pop();
+            }
+            match sections.last_mut() {
+                Some((open, count)) if *open == level => *count += 1,
+                _ => sections.push((level, closed.map_or(1, |(_, count)| count + 1))),
+            }
+
+            Some(
+                sections
+                    .iter()
+                    .map(|(_, count)| count.to_string())
+                    .collect::<Vec<_>>()
+                    .join("."),
+            )
+        })
+        .collect()
+}
+
+/// Puts the section number of each heading below `title_level` in front of its text, in a
+/// `<span class="heading-number">`. Being markup rather than text, the numbers are left
+/// out of the ids generated from the headings' text.
+pub fn number_headings(events: Vec<Event<'_>>, title_level: HeadingLevel) -> Vec<Event<'_>> {
+    let levels: Vec<HeadingLevel> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading(level, _, _)) => Some(*level),
+            _ => None,
+        })
+        .collect();
+    let mut numbers = heading_numbers(&levels, title_level).into_iter();
+
+    let mut numbered = Vec::with_capacity(events.len() + levels.len());
+    for event in events {
+        let number = match event {
+            Event::Start(Tag::Heading(..)) => numbers.next().flatten(),
+            _ => None,
+        };
+        numbered.push(event);
+        if let Some(number) = number {
+            numbered.push(Event::Html(CowStr::from(format!(
+                "<span class=\"heading-number\">{}</span> ",
+                number
+            ))));
+        }
+    }
+
+    numbered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use HeadingLevel::{H1, H2, H3, H4};
+
+    #[test]
+    fn headings_below_the_title_are_numbered() {
+        let numbers = heading_numbers(&[H1, H2, H3, H3, H2, H3, H1, H2], H1);
+        let numbers: Vec<Option<&str>> = numbers.iter().map(Option::as_deref).collect();
+        assert_eq!(
+            numbers,
+            [
+                None,
+                Some("1"),
+                Some("1.1"),
+                Some("1.2"),
+                Some("2"),
+                Some("2.1"),
+                None,
+                Some("1")
+            ]
+        );
+    }
+
+    #[test]
+    fn skipped_levels_go_one_number_deeper() {
+        let numbers = heading_numbers(&[H1, H2, H4, H3, H2], H1);
+        let numbers: Vec<Option<&str>> = numbers.iter().map(Option::as_deref).collect();
+        assert_eq!(
+            numbers,
+            [None, Some("1"), Some("1.1"), Some("1.2"), Some("2")]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use html_escape::encode_double_quoted_attribute;
+use regex::{Captures, Regex};
+use scraper::node::Element;
+use scraper::{Html, Selector};
+
+/// Calls `rewrite` with each opening `tag_name` tag found in the html (parsed with scraper),
+/// replacing the tag's markup with the returned string, or leaving it as is on `None`.
+/// Everything else in the html is passed through unchanged.
+pub fn rewrite_opening_tags<F>(html: &str, tag_name: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&str, &Element) -> Option<String>,
+{
+    let tag_re = Regex::new(&format!(r"(?i)<{}\b[^>]*>", regex::escape(tag_name))).unwrap();
+    let selector = Selector::parse(tag_name).unwrap();
+
+    tag_re
+        .replace_all(html, |caps: &Captures| {
+            let tag = &caps[0];
+            let fragment = Html::parse_fragment(tag);
+            fragment
+                .select(&selector)
+                .next()
+                .and_then(|element| rewrite(tag, element.value()))
+                .unwrap_or_else(|| tag.to_string())
+        })
+        .into_owned()
+}
+
+/// Builds an opening tag from a name and list of attributes, escaping the attribute values.
+pub fn opening_tag(tag_name: &str, attrs: &[(String, String)]) -> String {
+    let mut tag = format!("<{}", tag_name);
+    for (name, value) in attrs {
+        tag.push_str(&format!(
+            " {}=\"{}\"",
+            name,
+            encode_double_quoted_attribute(value)
+        ));
+    }
+    tag.push('>');
+
+    tag
+}
+
+/// Returns the attributes of an element in the form accepted by `opening_tag`.
+pub fn element_attrs(element: &Element) -> Vec<(String, String)> {
+    element
+        .attrs()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Sets the value of an attribute, replacing it if it's already present.
+pub fn set_attr(attrs: &mut Vec<(String, String)>, name: &str, value: &str) {
+    match attrs.iter_mut().find(|(n, _)| n == name) {
+        Some((_, v)) => *v = value.to_string(),
+        None => attrs.push((name.to_string(), value.to_string())),
+    }
+}
@@ -1,10 +1,22 @@
 pub fn get_index_page_template() -> &'static str {
-    let index_page_template: &str = r###"
+    r###"
 <!doctype html>
-<html>
+<html{% if lang %} lang="{{ lang | escape }}"{% endif %}>
 <head>
-<style>
-    html, body {
+{% if favicon_url -%}
+<link rel="icon" href="{{ favicon_url }}" type="{{ favicon_type }}">
+{% if touch_icon_url %}<link rel="apple-touch-icon" href="{{ touch_icon_url }}">
+{% endif -%}
+{% endif -%}
+<style{% if csp_nonce %} nonce="{{ csp_nonce | escape }}"{% endif %}>
+{% if font_size %}html {
+    font-size: {{ font_size }};
+}
+{% endif %}{% if content_width %}body {
+    max-width: {{ content_width }}px;
+    margin: 0 auto;
+}
+{% endif %}    html, body {
         display: flex;
         align-items: center;
         justify-content: center;
@@ -38,6 +50,55 @@ pub fn get_index_page_template() -> &'static str {
         justify-content: center;
     }
     
+    .row-item h1 {
+        color: #fafafa;
+    }
+
+    .row-item.with-excerpt {
+        flex-direction: column;
+    }
+
+    .row-item .excerpt {
+        color: #aaa;
+        font-size: 0.9em;
+        padding-top: 5px;
+    }
+
+    .row-item .read-more {
+        font-size: 0.9em;
+    }
+
+    .row-item .long-read {
+        color: #aaa;
+        font-size: 0.8em;
+        padding-left: 10px;
+    }
+
+    .row-item .date {
+        color: #aaa;
+        font-size: 0.9em;
+        padding-right: 10px;
+    }
+
+    .tag-cloud {
+        flex-wrap: wrap;
+        max-width: 500px;
+    }
+
+    .tag-cloud a {
+        margin: 0 5px;
+    }
+
+    .authors a {
+        margin-left: 10px;
+    }
+
+    .tag-cloud .weight-1 { font-size: 0.8em; }
+    .tag-cloud .weight-2 { font-size: 1em; }
+    .tag-cloud .weight-3 { font-size: 1.2em; }
+    .tag-cloud .weight-4 { font-size: 1.4em; }
+    .tag-cloud .weight-5 { font-size: 1.6em; }
+
     a {
         text-decoration: none;
     }
@@ -50,42 +111,315 @@ pub fn get_index_page_template() -> &'static str {
         font-weight: bold;
     }
 </style>
-</head>
+{% if head_extra %}{{ head_extra }}
+{% endif %}</head>
 
 <body>
-<div class="container">
+<main class="container">
+    {% if heading -%}
+        <div class="row-item"><h1>{{ heading | escape }}</h1></div>
+    {%- endif %}
     {% for page in pages -%}
-        <div class="row-item"><a href="{{ page.url }}">{{ page.title }}</a></div>
+        {% if show_excerpts and page.excerpt -%}
+        <div class="row-item with-excerpt"><div><span class="date">{{ page.date }}</span>{% if page.short_title %}<a href="{{ page.url }}" title="{{ page.title | escape }}">{{ page.short_title | escape }}</a>{% else %}<a href="{{ page.url }}">{{ page.title | escape }}</a>{% endif %}{% if page.long_read %}<span class="long-read">Long read</span>{% endif %}</div><div class="excerpt">{{ page.excerpt | safe }}</div>{% if page.truncated %}<a class="read-more" href="{{ page.read_more_url }}">Read more &rarr;</a>{% endif %}</div>
+        {%- else -%}
+        <div class="row-item"><span class="date">{{ page.date }}</span>{% if page.short_title %}<a href="{{ page.url }}" title="{{ page.title | escape }}">{{ page.short_title | escape }}</a>{% else %}<a href="{{ page.url }}">{{ page.title | escape }}</a>{% endif %}{% if page.long_read %}<span class="long-read">Long read</span>{% endif %}</div>
+        {%- endif %}
     {%- endfor %}
-</div>
-</body>
+    {% if archive_url -%}
+        <div class="row-item"><a href="{{ archive_url }}">Archive</a></div>
+    {%- endif %}
+    {% if authors -%}
+        <div class="row-item authors">Authors:
+        {%- for author in authors %}
+            <a href="{{ author.url }}">{{ author.name | escape }}</a>
+        {%- endfor %}
+        </div>
+    {%- endif %}
+    {% if tag_cloud -%}
+        <div class="row-item tag-cloud">
+        {%- for tag in tag_cloud %}
+            <a class="weight-{{ tag.weight }}" href="{{ tag.url }}" title="{{ tag.count }} posts">{{ tag.name | escape }}</a>
+        {%- endfor %}
+        </div>
+    {%- endif %}
+</main>
+{% if body_end_extra %}{{ body_end_extra }}
+{% endif %}</body>
 </html>
-"###;
+"###
+}
+
+pub fn get_archive_page_template() -> &'static str {
+    r###"
+<!doctype html>
+<html>
+<head>
+{% if favicon_url -%}
+<link rel="icon" href="{{ favicon_url }}" type="{{ favicon_type }}">
+{% if touch_icon_url %}<link rel="apple-touch-icon" href="{{ touch_icon_url }}">
+{% endif -%}
+{% endif -%}
+<style{% if csp_nonce %} nonce="{{ csp_nonce | escape }}"{% endif %}>
+{% if font_size %}html {
+    font-size: {{ font_size }};
+}
+{% endif %}{% if content_width %}body {
+    max-width: {{ content_width }}px;
+    margin: 0 auto;
+}
+{% endif %}    html, body {
+        background-color: #222;
+        color: #fafafa;
+        min-height: 100%;
+    }
+
+    .container {
+        margin: 0 auto;
+        max-width: 500px;
+        padding: 20px 0;
+    }
+
+    .count {
+        background-color: #444;
+        border-radius: 10px;
+        color: #aaa;
+        font-size: 0.6em;
+        padding: 2px 8px;
+        vertical-align: middle;
+    }
+
+    ul {
+        list-style: none;
+        padding-left: 0;
+    }
+
+    li .date {
+        color: #aaa;
+        font-size: 0.9em;
+        padding-right: 10px;
+    }
+
+    a {
+        text-decoration: none;
+    }
 
-    return index_page_template;
+    a, a:visited, a:hover, a:active {
+        color: #fafafa;
+    }
+
+    a:hover {
+        font-weight: bold;
+    }
+</style>
+{% if head_extra %}{{ head_extra }}
+{% endif %}</head>
+
+<body>
+<main class="container">
+    <h1><a href="{{ home_url }}">Archive</a> <span class="count">{{ post_count }}</span></h1>
+    {% for year in years -%}
+    <section class="year">
+        <h2>{{ year.year }} <span class="count">{{ year.count }}</span></h2>
+        {% for month in year.months -%}
+        <h3>{{ month.month }} <span class="count">{{ month.count }}</span></h3>
+        <ul>
+            {% for page in month.posts -%}
+            <li><span class="date">{{ page.date }}</span><a href="{{ page.url }}">{{ page.title | escape }}</a></li>
+            {% endfor -%}
+        </ul>
+        {% endfor -%}
+    </section>
+    {% endfor -%}
+</main>
+{% if body_end_extra %}{{ body_end_extra }}
+{% endif %}</body>
+</html>
+"###
 }
 
 pub fn get_html_template() -> &'static str {
-    let html_template: &str = r###"
+    r###"
 <!doctype html>
-<html>
+<html{% if lang %} lang="{{ lang | escape }}"{% endif %}>
 <head>
-<style>
-{{ css_from_source }}
+{% if canonical_url -%}
+<link rel="canonical" href="{{ canonical_url }}">
+{% endif -%}
+{% if og_image_url -%}
+<meta property="og:image" content="{{ og_image_url }}">
+{% endif -%}
+{% if favicon_url -%}
+<link rel="icon" href="{{ favicon_url }}" type="{{ favicon_type }}">
+{% if touch_icon_url %}<link rel="apple-touch-icon" href="{{ touch_icon_url }}">
+{% endif -%}
+{% endif -%}
+<style{% if csp_nonce %} nonce="{{ csp_nonce | escape }}"{% endif %}>
+{% if font_size %}html {
+    font-size: {{ font_size }};
+}
+{% endif %}{% if content_width %}body {
+    max-width: {{ content_width }}px;
+    margin: 0 auto;
+}
+{% endif %}{{ css_from_source }}
 
 img {
     max-width: 200px;
 }
 
+video {
+    max-width: 100%;
+}
+
+.align-left {
+    text-align: left;
+}
+
+.align-center {
+    text-align: center;
+}
+
+.align-right {
+    text-align: right;
+}
+
+blockquote footer {
+    font-size: 0.9em;
+    margin-top: 0.5em;
+}
+
+blockquote footer cite::before {
+    content: "\2014\00a0";
+}
+
+.long-read {
+    font-size: 0.9em;
+    font-style: italic;
+}
+{% if smooth_scroll %}
+@media (prefers-reduced-motion: no-preference) {
+    html {
+        scroll-behavior: smooth;
+    }
+}
+
+h1, h2, h3, h4, h5, h6 {
+    scroll-margin-top: {{ scroll_offset }}px;
+}
+{% endif %}{% if code_copy %}
+.code-block {
+    position: relative;
+}
+
+.code-block .copy-code {
+    position: absolute;
+    top: 5px;
+    right: 5px;
+    font-size: 0.8em;
+    cursor: pointer;
+}
+{% endif %}{% if print_css %}
+@media print {
+{{ print_css }}
+}
+{% endif %}
 </style>
-</head>
+{% if stylesheet_url and csp_nonce -%}
+<link rel="stylesheet" href="{{ stylesheet_url }}" media="print" id="site-stylesheet">
+<script nonce="{{ csp_nonce | escape }}">
+var siteStylesheet = document.getElementById('site-stylesheet');
+if (siteStylesheet.sheet) {
+    siteStylesheet.media = 'all';
+} else {
+    siteStylesheet.onload = function () { siteStylesheet.media = 'all'; };
+}
+</script>
+<noscript><link rel="stylesheet" href="{{ stylesheet_url }}"></noscript>
+{% elif stylesheet_url -%}
+<link rel="stylesheet" href="{{ stylesheet_url }}" media="print" onload="this.media='all'">
+<noscript><link rel="stylesheet" href="{{ stylesheet_url }}"></noscript>
+{% endif -%}
+{% if head_extra %}{{ head_extra }}
+{% endif %}</head>
 
 <body>
+{% if site_header -%}
+<header class="site-header"><a href="{{ home_url }}">{{ site_title | escape }}</a></header>
+{% endif -%}
+{% if languages and languages | length > 1 -%}
+<nav class="languages">
+{% for language in languages %}{% if language.lang != lang %}<a href="{{ language.url }}" hreflang="{{ language.lang | escape }}">{{ language.lang | escape }}</a> {% endif %}{% endfor %}
+</nav>
+{% endif -%}
+{% if series -%}
+<nav class="series">
+Part {{ series_part }} of {{ series_parts }} in <a href="{{ series_url }}">{{ series | escape }}</a>
+{% if series_prev_url %}<a href="{{ series_prev_url }}">&larr; Previous part</a>{% endif %}
+{% if series_next_url %}<a href="{{ series_next_url }}">Next part &rarr;</a>{% endif %}
+</nav>
+{% endif -%}
+{% if hero_video %}{{ hero_video }}
+{% endif -%}
+{% if long_read -%}
+<p class="long-read">Long read, {{ word_count }} words</p>
+{% endif -%}
+<article{% if content_class %} class="{{ content_class | escape }}"{% endif %}{% if data_attributes %} {{ data_attributes }}{% endif %}>
 {{ body_content }}
-</body>
+</article>
+{% if updated and updated != published -%}
+<footer class="last-updated">Published {{ published }}, updated {{ updated }}</footer>
+{% endif -%}
+{% if code_copy -%}
+<script{% if csp_nonce %} nonce="{{ csp_nonce | escape }}"{% endif %}>
+document.querySelectorAll(".copy-code").forEach(function (button) {
+    button.addEventListener("click", function () {
+        var code = button.parentElement.querySelector("pre");
+        navigator.clipboard.writeText(code.innerText).then(function () {
+            button.textContent = "Copied";
+            setTimeout(function () { button.textContent = "Copy"; }, 2000);
+        });
+    });
+});
+</script>
+{% endif -%}
+{% if body_end_extra %}{{ body_end_extra }}
+{% endif %}</body>
 
 </html>
-"###;
+"###
+}
+
+/// The styles for printing posts, used when print styles are asked for without a stylesheet
+/// of their own: the navigation is left out, the content takes up the whole page and the
+/// links have their urls printed after them.
+pub fn get_print_css() -> &'static str {
+    r#"body {
+    max-width: none;
+    margin: 0;
+    color: #000;
+    background: #fff;
+}
+
+.site-header, nav.languages, nav.series, nav.toc, .copy-code {
+    display: none;
+}
+
+img {
+    max-width: 100%;
+}
+
+pre, blockquote, img, figure {
+    break-inside: avoid;
+}
+
+h1, h2, h3, h4, h5, h6 {
+    break-after: avoid;
+}
 
-    return html_template;
+a[href^="http"]::after {
+    content: " (" attr(href) ")";
+    font-size: 0.9em;
+    word-break: break-all;
+}"#
 }
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// The `.json` files the generator writes to the top of the output directory itself, which
+/// the `.json` file of a post (like `posts.md`) mustn't overwrite.
+const RESERVED_FILE_NAMES: [&str; 5] = [
+    "posts.json",
+    "feed.json",
+    "manifest.json",
+    "changes.json",
+    "etags.json",
+];
+
+/// Whether the `.json` file of a post, at `json_file_name` under the output directory,
+/// would overwrite one of the files the generator writes there itself.
+pub fn is_reserved_file_name(json_file_name: &str) -> bool {
+    RESERVED_FILE_NAMES.contains(&json_file_name)
+}
+
+/// A post as written to its own `.json` file (next to its `.html`), for frontends that
+/// render the posts themselves:
+///
+/// ```text
+/// {
+///   "title": "Hello World",
+///   "url": "https://example.com/hello_world.html",
+///   "published": "2024-01-13T00:00:00+00:00",
+///   "updated": "2024-01-13T00:00:00+00:00",
+///   "tags": ["intro"],
+///   "excerpt": "<p>...</p>",
+///   "body_html": "<h1>Hello World</h1>\n<p>...</p>\n",
+///   "meta": { "author": "..." }
+/// }
+/// ```
+#[derive(Clone, Debug, Serialize)]
+pub struct JsonPost {
+    pub title: String,
+    /// the url of the post's html page
+    pub url: String,
+    /// RFC 3339 timestamps
+    pub published: String,
+    pub updated: String,
+    pub tags: Vec<String>,
+    pub excerpt: String,
+    pub body_html: String,
+    /// the front matter keys that aren't used by the generator itself
+    pub meta: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// A post as listed in `posts.json`, the array of every post in index order: its
+/// `JsonPost` without the body, and with the url of its `.json` file as `json_url`.
+#[derive(Clone, Debug, Serialize)]
+pub struct JsonPostSummary {
+    pub title: String,
+    pub url: String,
+    pub json_url: String,
+    pub published: String,
+    pub updated: String,
+    pub tags: Vec<String>,
+    pub excerpt: String,
+}
+
+impl JsonPost {
+    pub fn summary(&self, json_url: String) -> JsonPostSummary {
+        JsonPostSummary {
+            title: self.title.clone(),
+            url: self.url.clone(),
+            json_url,
+            published: self.published.clone(),
+            updated: self.updated.clone(),
+            tags: self.tags.clone(),
+            excerpt: self.excerpt.clone(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_file_names_are_only_at_the_top() {
+        assert!(is_reserved_file_name("posts.json"));
+        assert!(is_reserved_file_name("feed.json"));
+        assert!(!is_reserved_file_name("2024/posts.json"));
+        assert!(!is_reserved_file_name("hello_world.json"));
+    }
+
+    #[test]
+    fn titles_are_escaped_once() {
+        let post = JsonPost {
+            title: "Say \"hi\" \\ bye".to_string(),
+            url: String::new(),
+            published: String::new(),
+            updated: String::new(),
+            tags: Vec::new(),
+            excerpt: String::new(),
+            body_html: String::new(),
+            meta: BTreeMap::new(),
+        };
+        let json: serde_json::Value = serde_json::from_str(&post.to_json()).unwrap();
+        assert_eq!(json["title"], "Say \"hi\" \\ bye");
+    }
+}
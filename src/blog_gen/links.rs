@@ -0,0 +1,215 @@
+use super::html_rewrite::{element_attrs, opening_tag, rewrite_opening_tags, set_attr};
+use super::redirects::site_path;
+
+/// Returns true for links to other sites: absolute http(s) urls not under `base_url`.
+pub fn is_external_link(href: &str, base_url: &str) -> bool {
+    let is_absolute = href.starts_with("http://") || href.starts_with("https://");
+    let is_own_site = !base_url.is_empty() && href.starts_with(base_url);
+
+    is_absolute && !is_own_site
+}
+
+/// Makes external links open in a new tab, with `rel="noopener noreferrer"` so that the
+/// opened page gets no access to the post (or the referrer). Any `rel` values the link
+/// already has are kept.
+pub fn open_external_links_in_new_tab(html: &str, base_url: &str) -> String {
+    rewrite_opening_tags(html, "a", |_, a| {
+        if !is_external_link(a.attr("href")?, base_url) {
+            return None;
+        }
+
+        let mut rel: Vec<&str> = a.attr("rel").unwrap_or("").split_whitespace().collect();
+        for value in ["noopener", "noreferrer"] {
+            if !rel.contains(&value) {
+                rel.push(value);
+            }
+        }
+
+        let mut attrs = element_attrs(a);
+        set_attr(&mut attrs, "target", "_blank");
+        set_attr(&mut attrs, "rel", &rel.join(" "));
+
+        Some(opening_tag("a", &attrs))
+    })
+}
+
+/// Resolves the relative urls of links, images and media in the html against `page_url`
+/// (the absolute url of the page the html is from), for html shown away from the page,
+/// as in a feed reader.
+pub fn absolute_links(html: &str, page_url: &str) -> String {
+    rewrite_link_urls(html, |url| resolve_url(page_url, url))
+}
+
+/// Rewrites the relative urls of links, images and media in the html of the page at
+/// `page_path` (within the rendered outputs dir) to be relative to the root of the dir
+/// instead, for html moved from the page to one at the root. Fragment links are left
+/// alone.
+pub fn rebase_links(html: &str, page_path: &str) -> String {
+    rewrite_link_urls(html, |url| {
+        if url.is_empty() || url.starts_with(['/', '#']) || url.contains(':') {
+            return None;
+        }
+        let path = join_path(&format!("/{}", page_path), url);
+        Some(path.trim_start_matches('/').to_string())
+    })
+}
+
+/// Rewrites the links, images and media in the html of the page at `page_path` that
+/// point into the site at the absolute `base_url` (by its full url, or by its path from the
+/// root of the host) into urls relative to the page, so that the site works wherever it's
+/// served from.
+pub fn relative_links(html: &str, base_url: &str, page_path: &str) -> String {
+    if !base_url.contains("://") {
+        return html.to_string();
+    }
+    let base_path = site_path(base_url, "");
+    let up = match page_path.matches('/').count() {
+        0 => "./".to_string(),
+        depth => "../".repeat(depth),
+    };
+
+    rewrite_link_urls(html, |url| {
+        let path = match url.strip_prefix(base_url) {
+            Some(path) => path,
+            None if url.starts_with("//") => return None,
+            None => url.strip_prefix(&base_path)?,
+        };
+        Some(format!("{}{}", up, path.trim_start_matches('/')))
+    })
+}
+
+/// Calls `rewrite` with each of the urls of links, images and media in the html, replacing
+/// the ones it returns a new url for.
+fn rewrite_link_urls<F>(html: &str, rewrite: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let attrs_by_tag = [
+        ("a", &["href"][..]),
+        ("img", &["src"]),
+        ("source", &["srcset"]),
+        ("video", &["src", "poster"]),
+        ("audio", &["src"]),
+    ];
+
+    attrs_by_tag
+        .iter()
+        .fold(html.to_string(), |html, (tag, names)| {
+            rewrite_opening_tags(&html, tag, |_, element| {
+                let mut attrs = element_attrs(element);
+                let mut rewritten = false;
+                for name in names.iter() {
+                    if let Some(url) = element.attr(name).and_then(&rewrite) {
+                        set_attr(&mut attrs, name, &url);
+                        rewritten = true;
+                    }
+                }
+                rewritten.then(|| opening_tag(tag, &attrs))
+            })
+        })
+}
+
+/// The absolute url of `url` on the page at `page_url`, or `None` if it's absolute
+/// already (or not something that can be resolved, like a `mailto:` link).
+fn resolve_url(page_url: &str, url: &str) -> Option<String> {
+    if url.is_empty() || url.starts_with("//") || url.contains(':') && !url.starts_with('/') {
+        return None;
+    }
+
+    let (scheme, rest) = page_url.split_once("://")?;
+    let origin_len = scheme.len() + 3 + rest.find('/').unwrap_or(rest.len());
+    let (origin, page_path) = page_url.split_at(origin_len);
+
+    if url.starts_with('#') {
+        let page_url = page_url.split('#').next().unwrap_or(page_url);
+        return Some(format!("{}{}", page_url, url));
+    }
+    if url.starts_with('/') {
+        return Some(format!("{}{}", origin, url));
+    }
+
+    Some(format!("{}{}", origin, join_path(page_path, url)))
+}
+
+/// The path of the relative `url` on the page at `page_path`: the dir of the page, with
+/// the `..` and `.` segments of the url applied to it.
+fn join_path(page_path: &str, url: &str) -> String {
+    let mut segments: Vec<&str> = page_path.split('/').filter(|s| !s.is_empty()).collect();
+    if !page_path.ends_with('/') {
+        segments.pop();
+    }
+    let (url_path, suffix) = match url.find(['?', '#']) {
+        Some(pos) => url.split_at(pos),
+        None => (url, ""),
+    };
+    let mut url_segments = url_path.split('/').peekable();
+    while let Some(segment) = url_segments.next() {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." => {}
+            // keep a trailing `/`, as in `docs/`
+            "" if url_segments.peek().is_none() => segments.push(""),
+            "" => {}
+            segment => segments.push(segment),
+        }
+    }
+
+    format!("/{}{}", segments.join("/"), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://example.com/blog/";
+
+    #[test]
+    fn links_from_a_post_two_levels_deep_go_back_up_to_the_root() {
+        let html = r#"<a href="https://example.com/blog/about.html">about</a>"#;
+        assert_eq!(
+            relative_links(html, BASE_URL, "2024/05/post.html"),
+            r#"<a href="../../about.html">about</a>"#
+        );
+        let html = r#"<img src="/blog/images/a.png">"#;
+        assert_eq!(
+            relative_links(html, BASE_URL, "2024/05/post.html"),
+            r#"<img src="../../images/a.png">"#
+        );
+    }
+
+    #[test]
+    fn links_from_a_root_page_stay_in_the_same_dir() {
+        let html = r#"<a href="https://example.com/blog/about.html">about</a>"#;
+        assert_eq!(
+            relative_links(html, BASE_URL, "post.html"),
+            r#"<a href="./about.html">about</a>"#
+        );
+    }
+
+    #[test]
+    fn links_away_from_the_site_are_left_alone() {
+        let html = r#"<a href="https://example.org/blog/a.html">a</a><a href="//cdn.example.com/b.js">b</a><a href="c.html">c</a>"#;
+        assert_eq!(relative_links(html, BASE_URL, "2024/post.html"), html);
+    }
+
+    #[test]
+    fn only_external_links_open_in_a_new_tab() {
+        let html = concat!(
+            r#"<a href="https://example.org/a.html">a</a>"#,
+            r#"<a href="https://example.com/blog/b.html">b</a>"#,
+            r##"<a href="#c">c</a>"##,
+            r#"<a href="mailto:d@example.com">d</a>"#,
+        );
+        assert_eq!(
+            open_external_links_in_new_tab(html, BASE_URL),
+            concat!(
+                r#"<a href="https://example.org/a.html" target="_blank" rel="noopener noreferrer">a</a>"#,
+                r#"<a href="https://example.com/blog/b.html">b</a>"#,
+                r##"<a href="#c">c</a>"##,
+                r#"<a href="mailto:d@example.com">d</a>"#,
+            )
+        );
+    }
+}
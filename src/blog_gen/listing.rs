@@ -0,0 +1,92 @@
+use std::fmt;
+
+/// A post that a build would publish, as shown by `--list`.
+#[derive(Clone, Debug)]
+pub struct PostListing {
+    /// the markdown source file the post comes from
+    pub source: String,
+    pub title: String,
+    /// the date the post is listed under, in the generator's date format
+    pub date: String,
+    pub tags: Vec<String>,
+    pub draft: bool,
+}
+
+/// The posts of a build, laid out as a table with a column per field.
+#[derive(Clone, Debug, Default)]
+pub struct PostList {
+    pub posts: Vec<PostListing>,
+}
+
+impl fmt::Display for PostList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<[String; 5]> = self
+            .posts
+            .iter()
+            .map(|post| {
+                [
+                    post.source.clone(),
+                    post.title.clone(),
+                    post.date.clone(),
+                    post.tags.join(", "),
+                    if post.draft { "yes" } else { "no" }.to_string(),
+                ]
+            })
+            .collect();
+
+        let header = ["FILE", "TITLE", "DATE", "TAGS", "DRAFT"].map(str::to_string);
+        let mut widths = header.clone().map(|column| column.chars().count());
+        for row in rows.iter() {
+            for (width, column) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(column.chars().count());
+            }
+        }
+
+        for (i, row) in std::iter::once(&header).chain(rows.iter()).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let line: Vec<String> = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(column, width)| format!("{:<width$}", column, width = width))
+                .collect();
+            write!(f, "{}", line.join("  ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posts_are_laid_out_in_columns() {
+        let list = PostList {
+            posts: vec![
+                PostListing {
+                    source: "a.md".to_string(),
+                    title: "A longer title".to_string(),
+                    date: "2024-01-01".to_string(),
+                    tags: vec!["rust".to_string(), "meta".to_string()],
+                    draft: false,
+                },
+                PostListing {
+                    source: "bb.md".to_string(),
+                    title: "B".to_string(),
+                    date: "2024-01-02".to_string(),
+                    tags: Vec::new(),
+                    draft: true,
+                },
+            ],
+        };
+        assert_eq!(
+            list.to_string(),
+            "FILE   TITLE           DATE        TAGS        DRAFT\n\
+             a.md   A longer title  2024-01-01  rust, meta  no\n\
+             bb.md  B               2024-01-02              yes"
+        );
+    }
+}
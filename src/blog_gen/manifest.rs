@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// The posts written by a build, keyed by their path in the rendered outputs dir, so that
+/// the next build can tell what it changed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub posts: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// the markdown source file the post was rendered from
+    pub source: String,
+    pub title: String,
+    /// the sha256 of the post's markdown
+    pub content_hash: String,
+}
+
+/// A post whose title is different from the one in the previous build.
+#[derive(Clone, Debug, Serialize)]
+pub struct Retitle {
+    pub path: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// How the posts of a build differ from the ones of the build before it, by path.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ChangeReport {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub retitled: Vec<Retitle>,
+    /// posts whose markdown has changed
+    pub changed: Vec<String>,
+}
+
+impl Manifest {
+    /// Reads the manifest left by an earlier build, if there is one.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// What has changed in this manifest since the `previous` one.
+    pub fn changes_since(&self, previous: &Manifest) -> ChangeReport {
+        let mut report = ChangeReport::default();
+
+        for (path, entry) in self.posts.iter() {
+            let Some(previous_entry) = previous.posts.get(path) else {
+                report.added.push(path.clone());
+                continue;
+            };
+
+            if entry.title != previous_entry.title {
+                report.retitled.push(Retitle {
+                    path: path.clone(),
+                    from: previous_entry.title.clone(),
+                    to: entry.title.clone(),
+                });
+            }
+            if entry.content_hash != previous_entry.content_hash {
+                report.changed.push(path.clone());
+            }
+        }
+
+        report.removed = previous
+            .posts
+            .keys()
+            .filter(|path| !self.posts.contains_key(*path))
+            .cloned()
+            .collect();
+
+        report
+    }
+}
+
+impl ChangeReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.retitled.is_empty()
+            && self.changed.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for ChangeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes since the last build");
+        }
+
+        write!(f, "changes since the last build:")?;
+        for path in self.added.iter() {
+            write!(f, "\n  added: {}", path)?;
+        }
+        for path in self.removed.iter() {
+            write!(f, "\n  removed: {}", path)?;
+        }
+        for retitle in self.retitled.iter() {
+            write!(
+                f,
+                "\n  retitled: {} ({:?} -> {:?})",
+                retitle.path, retitle.from, retitle.to
+            )?;
+        }
+        for path in self.changed.iter() {
+            write!(f, "\n  changed: {}", path)?;
+        }
+
+        Ok(())
+    }
+}
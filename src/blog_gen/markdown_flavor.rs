@@ -0,0 +1,47 @@
+use pulldown_cmark::Options;
+
+/// Presets for the markdown extensions that posts are rendered with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MarkdownFlavor {
+    /// plain CommonMark, with no extensions at all
+    #[value(name = "commonmark")]
+    CommonMark,
+
+    /// tables, task lists and strikethrough, as on GitHub. Autolinks in angle brackets are
+    /// part of CommonMark itself, bare urls are not linked.
+    Github,
+
+    /// tables, footnotes, strikethrough, task lists and smart punctuation
+    #[default]
+    Default,
+
+    /// everything in `Default`, plus heading attributes (`# Heading {#id .class}`)
+    Full,
+}
+
+impl MarkdownFlavor {
+    pub fn options(&self) -> Options {
+        let mut options = Options::empty();
+
+        match self {
+            MarkdownFlavor::CommonMark => {}
+            MarkdownFlavor::Github => {
+                options.insert(Options::ENABLE_TABLES);
+                options.insert(Options::ENABLE_TASKLISTS);
+                options.insert(Options::ENABLE_STRIKETHROUGH);
+            }
+            MarkdownFlavor::Default | MarkdownFlavor::Full => {
+                options.insert(Options::ENABLE_STRIKETHROUGH);
+                options.insert(Options::ENABLE_TABLES);
+                options.insert(Options::ENABLE_FOOTNOTES);
+                options.insert(Options::ENABLE_TASKLISTS);
+                options.insert(Options::ENABLE_SMART_PUNCTUATION);
+                if *self == MarkdownFlavor::Full {
+                    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+                }
+            }
+        }
+
+        options
+    }
+}
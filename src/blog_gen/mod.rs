@@ -1,2 +1,52 @@
+pub mod archive;
+pub mod archive_layout;
+pub mod assets;
+pub mod blockquotes;
 pub mod blog_generator;
+pub mod blogroll;
+pub mod build_lock;
+pub mod build_summary;
+pub mod bundle;
+pub mod checks;
+pub mod code_copy;
+pub mod config_file;
+pub mod css_imports;
+pub mod excerpt;
+pub mod feed;
+pub mod figures;
+pub mod file_mode;
+pub mod front_matter;
+pub mod git_dates;
+pub mod heading_numbers;
+pub mod html_rewrite;
 pub mod html_template;
+pub mod json_output;
+pub mod links;
+pub mod listing;
+pub mod manifest;
+pub mod markdown_flavor;
+pub mod og_image;
+pub mod output_format;
+pub mod partials;
+pub mod pdf;
+pub mod post_order;
+pub mod precompress;
+pub mod problems;
+pub mod reading;
+pub mod recent;
+pub mod redirects;
+pub mod render_cache;
+pub mod sanitize;
+pub mod scaffold;
+pub mod shortcodes;
+pub mod since;
+pub mod single_page;
+pub mod site;
+pub mod slug;
+pub mod source_encoding;
+pub mod tables;
+pub mod tags;
+pub mod template_rules;
+pub mod theme;
+pub mod toc;
+pub mod watch;
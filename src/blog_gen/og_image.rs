@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::Path;
+
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+
+/// The size of an Open Graph image, as recommended for the cards of most social sites.
+pub const OG_IMAGE_WIDTH: u32 = 1200;
+pub const OG_IMAGE_HEIGHT: u32 = 630;
+
+const MARGIN: u32 = 80;
+const TITLE_SIZE: f32 = 72.0;
+const SITE_NAME_SIZE: f32 = 36.0;
+const MAX_TITLE_LINES: usize = 4;
+
+const BACKGROUND: Rgb<u8> = Rgb([34, 34, 34]);
+const TEXT: Rgb<u8> = Rgb([250, 250, 250]);
+
+/// The fonts tried, in order, when no font is given for the Open Graph images: the common
+/// sans-serif fonts of Linux, macOS and Windows.
+const DEFAULT_FONTS: [&str; 5] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Bold.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+    "C:\\Windows\\Fonts\\arialbd.ttf",
+];
+
+/// Loads the TrueType (or OpenType) font at `path`, or else the first of the common system
+/// fonts there is, to draw the text of the Open Graph images with.
+pub fn load_font(path: Option<&Path>) -> Result<FontVec, String> {
+    let path = match path {
+        Some(path) => path,
+        None => DEFAULT_FONTS
+            .iter()
+            .map(Path::new)
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                "none of the default fonts is installed, so a font is needed".to_string()
+            })?,
+    };
+
+    let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    FontVec::try_from_vec(data).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Draws the Open Graph image of a post: its title (wrapped over up to four lines) with the
+/// site name below, in `font` on `background` (scaled and cropped to fit) or a plain dark
+/// background. Characters the font doesn't have are drawn as its missing glyph.
+pub fn og_image(
+    title: &str,
+    site_name: Option<&str>,
+    background: Option<&DynamicImage>,
+    font: &FontVec,
+) -> RgbImage {
+    let mut image = match background {
+        Some(background) => background
+            .resize_to_fill(OG_IMAGE_WIDTH, OG_IMAGE_HEIGHT, FilterType::Triangle)
+            .to_rgb8(),
+        None => RgbImage::from_pixel(OG_IMAGE_WIDTH, OG_IMAGE_HEIGHT, BACKGROUND),
+    };
+
+    let title_font = font.as_scaled(PxScale::from(TITLE_SIZE));
+    let max_width = (OG_IMAGE_WIDTH - 2 * MARGIN) as f32;
+    let lines = wrap(
+        title,
+        |text| text_width(&title_font, text),
+        max_width,
+        MAX_TITLE_LINES,
+    );
+    let line_height = title_font.height() + title_font.line_gap();
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = MARGIN as f32 + title_font.ascent() + i as f32 * line_height;
+        draw_text(&mut image, &title_font, line, MARGIN as f32, baseline);
+    }
+
+    if let Some(site_name) = site_name {
+        let site_name_font = font.as_scaled(PxScale::from(SITE_NAME_SIZE));
+        let baseline = (OG_IMAGE_HEIGHT - MARGIN) as f32 + site_name_font.descent();
+        draw_text(
+            &mut image,
+            &site_name_font,
+            site_name,
+            MARGIN as f32,
+            baseline,
+        );
+    }
+
+    image
+}
+
+/// Wraps `text` at word boundaries into lines no wider than `max_width` (going by `width`),
+/// ending with an ellipsis when it takes more than `max_lines`.
+fn wrap<W>(text: &str, width: W, max_width: f32, max_lines: usize) -> Vec<String>
+where
+    W: Fn(&str) -> f32,
+{
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        // words too long for a line of their own get split over several
+        for piece in split_to_width(word, &width, max_width) {
+            if line.is_empty() {
+                line = piece;
+            } else if width(&format!("{} {}", line, piece)) <= max_width {
+                line.push(' ');
+                line.push_str(&piece);
+            } else {
+                lines.push(std::mem::replace(&mut line, piece));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.last_mut().unwrap();
+        let mut kept: Vec<char> = last.chars().collect();
+        while !kept.is_empty()
+            && width(&format!(
+                "{}...",
+                kept.iter().collect::<String>().trim_end()
+            )) > max_width
+        {
+            kept.pop();
+        }
+        *last = format!("{}...", kept.iter().collect::<String>().trim_end());
+    }
+
+    lines
+}
+
+/// Splits a word into pieces no wider than `max_width` (each of at least one character).
+fn split_to_width<W>(word: &str, width: &W, max_width: f32) -> Vec<String>
+where
+    W: Fn(&str) -> f32,
+{
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for c in word.chars() {
+        piece.push(c);
+        if width(&piece) > max_width && piece.chars().count() > 1 {
+            piece.pop();
+            pieces.push(std::mem::replace(&mut piece, c.to_string()));
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+
+    pieces
+}
+
+/// The width of `text` laid out on one line, with kerning.
+fn text_width<F: Font>(font: &impl ScaleFont<F>, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, glyph_id);
+        }
+        width += font.h_advance(glyph_id);
+        previous = Some(glyph_id);
+    }
+
+    width
+}
+
+/// Draws `text` from `x` along the `baseline`, blending it with what's underneath so the
+/// edges of the glyphs are smooth.
+fn draw_text<F: Font>(
+    image: &mut RgbImage,
+    font: &impl ScaleFont<F>,
+    text: &str,
+    x: f32,
+    baseline: f32,
+) {
+    let mut caret = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, glyph_id);
+        }
+        let glyph = glyph_id.with_scale_and_position(font.scale(), point(caret, baseline));
+        caret += font.h_advance(glyph_id);
+        previous = Some(glyph_id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|dx, dy, coverage| {
+            let pixel_x = bounds.min.x as i64 + dx as i64;
+            let pixel_y = bounds.min.y as i64 + dy as i64;
+            if pixel_x < 0
+                || pixel_y < 0
+                || pixel_x >= image.width() as i64
+                || pixel_y >= image.height() as i64
+            {
+                return;
+            }
+            let pixel = image.get_pixel_mut(pixel_x as u32, pixel_y as u32);
+            for channel in 0..3 {
+                let under = pixel.0[channel] as f32;
+                let over = TEXT.0[channel] as f32;
+                pixel.0[channel] = (under + (over - under) * coverage.min(1.0)).round() as u8;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a monospaced width, one per character
+    fn chars(text: &str) -> f32 {
+        text.chars().count() as f32
+    }
+
+    #[test]
+    fn titles_wrap_at_word_boundaries() {
+        assert_eq!(
+            wrap("the quick brown fox", chars, 10.0, 4),
+            vec!["the quick", "brown fox"]
+        );
+    }
+
+    #[test]
+    fn long_words_are_split_and_long_titles_cut_short() {
+        assert_eq!(
+            wrap("abcdefghijkl", chars, 5.0, 4),
+            vec!["abcde", "fghij", "kl"]
+        );
+        assert_eq!(
+            wrap("one two three four five", chars, 5.0, 2),
+            vec!["one", "tw..."]
+        );
+    }
+
+    #[test]
+    fn non_ascii_titles_are_drawn() {
+        let Ok(font) = load_font(None) else {
+            eprintln!("skipping, no default font is installed");
+            return;
+        };
+        let image = og_image("Ünïcödé — ñ", None, None, &font);
+        let drawn = image.pixels().filter(|pixel| **pixel != BACKGROUND).count();
+        assert!(drawn > 0);
+        assert_ne!(font.glyph_id('ñ'), font.glyph_id('?'));
+    }
+
+    #[test]
+    fn a_missing_font_is_an_error() {
+        assert!(load_font(Some(Path::new("/no/such/font.ttf"))).is_err());
+    }
+}
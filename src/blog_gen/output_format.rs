@@ -0,0 +1,92 @@
+/// The line endings that output files can be normalized to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+
+    /// `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// The UTF-8 byte order mark, which some Windows tools expect at the start of a file.
+pub const UTF8_BOM: char = '\u{feff}';
+
+/// Applies the final formatting to the contents of an output file: converting every line
+/// ending to `line_ending` (if given), making sure the file ends with exactly one line
+/// ending when `final_newline` is set, and starting it with a byte order mark when `bom`
+/// is set.
+pub fn format_output(
+    contents: &str,
+    final_newline: bool,
+    line_ending: Option<LineEnding>,
+    bom: bool,
+) -> String {
+    let mut formatted = match line_ending {
+        Some(line_ending) => contents
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join(line_ending.as_str()),
+        None => contents.to_string(),
+    };
+
+    if final_newline {
+        let newline = match line_ending {
+            Some(line_ending) => line_ending.as_str(),
+            None if formatted.contains("\r\n") => "\r\n",
+            None => "\n",
+        };
+        let trimmed_len = formatted.trim_end_matches(['\r', '\n']).len();
+        formatted.truncate(trimmed_len);
+        formatted.push_str(newline);
+    }
+
+    if bom && !formatted.starts_with(UTF8_BOM) {
+        formatted.insert(0, UTF8_BOM);
+    }
+
+    formatted
+}
+
+/// Converts the `\r\n` line endings of markdown written on Windows to `\n`, before any of
+/// it is parsed, since the front matter delimiters and the excerpt marker are matched
+/// line by line.
+pub fn normalize_line_endings(md_content: String) -> String {
+    if md_content.contains('\r') {
+        md_content.replace("\r\n", "\n")
+    } else {
+        md_content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_line_endings_are_normalized() {
+        assert_eq!(normalize_line_endings("a\r\nb\r\n".to_string()), "a\nb\n");
+        // a lone carriage return isn't a line ending
+        assert_eq!(normalize_line_endings("a\rb\n".to_string()), "a\rb\n");
+    }
+
+    #[test]
+    fn the_bom_is_added_once_at_the_start() {
+        let formatted = format_output("a\nb", true, Some(LineEnding::Crlf), true);
+        assert_eq!(formatted, "\u{feff}a\r\nb\r\n");
+
+        // contents that already start with one don't get another
+        let formatted = format_output(&formatted, true, Some(LineEnding::Crlf), true);
+        assert_eq!(formatted.matches(UTF8_BOM).count(), 1);
+        assert!(formatted.starts_with(UTF8_BOM));
+    }
+}
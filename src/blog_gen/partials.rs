@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use super::output_format::normalize_line_endings;
+
+/// How deeply partials may include other partials.
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Splices the partials named by the include directives in a post's markdown into it, so
+/// that boilerplate like an author bio can be written once and shared by every post:
+///
+/// ```text
+/// {{ include "partials/bio.md" }}
+/// ```
+///
+/// The partials are read from `partials_dir`, and may include other partials (nested up to
+/// `MAX_INCLUDE_DEPTH` deep, and never themselves). Directives inside fenced code blocks are
+/// left alone.
+pub fn expand_includes(md_content: &str, partials_dir: &Path) -> Result<String, String> {
+    expand(md_content, partials_dir, &mut Vec::new())
+}
+
+fn expand(
+    md_content: &str,
+    partials_dir: &Path,
+    including: &mut Vec<String>,
+) -> Result<String, String> {
+    let include = Regex::new(r#"\{\{\s*include\s+"([^"]+)"\s*\}\}"#).unwrap();
+
+    let mut in_fence = false;
+    let mut expanded = String::with_capacity(md_content.len());
+
+    for line in md_content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            expanded.push_str(line);
+            continue;
+        }
+
+        let mut rest = 0;
+        for caps in include.captures_iter(line) {
+            let directive = caps.get(0).unwrap();
+            let name = caps[1].to_string();
+            expanded.push_str(&line[rest..directive.start()]);
+            rest = directive.end();
+
+            if including.contains(&name) {
+                including.push(name);
+                return Err(format!(
+                    "the partials include each other: {}",
+                    including.join(" -> ")
+                ));
+            }
+            if including.len() == MAX_INCLUDE_DEPTH {
+                return Err(format!(
+                    "the partial \"{}\" is nested more than {} includes deep",
+                    name, MAX_INCLUDE_DEPTH
+                ));
+            }
+
+            let partial = fs::read_to_string(partials_dir.join(&name))
+                .map(normalize_line_endings)
+                .map_err(|e| {
+                    format!(
+                        "the partial \"{}\" can't be read from {}: {}",
+                        name,
+                        partials_dir.display(),
+                        e
+                    )
+                })?;
+            including.push(name);
+            let partial = expand(&partial, partials_dir, including)?;
+            including.pop();
+            // the line the directive is on already ends where it should
+            expanded.push_str(partial.strip_suffix('\n').unwrap_or(&partial));
+        }
+        expanded.push_str(&line[rest..]);
+    }
+
+    Ok(expanded)
+}
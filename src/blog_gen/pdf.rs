@@ -0,0 +1,71 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The html to PDF renderers looked for on the `PATH`, in order of preference. None of
+/// them ship with the generator; one has to be installed for PDF export to work.
+const PDF_RENDERERS: [&str; 5] = [
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "wkhtmltopdf",
+];
+
+/// The first of the known html to PDF renderers found on the `PATH`.
+pub fn find_pdf_renderer() -> Option<String> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path).find_map(|dir| {
+        PDF_RENDERERS
+            .iter()
+            .map(|renderer| dir.join(renderer))
+            .find(|candidate| candidate.is_file())
+            .map(|renderer| format!("{}", renderer.display()))
+    })
+}
+
+/// Prints the html page at `html_path` to a PDF at `pdf_path`, using either a headless
+/// Chrome/Chromium or wkhtmltopdf as the `renderer`. The page is loaded from the file, so
+/// its inline css applies and images next to it are embedded.
+pub fn html_to_pdf(renderer: &str, html_path: &Path, pdf_path: &Path) -> Result<(), String> {
+    let html_path = html_path.canonicalize().map_err(|e| e.to_string())?;
+
+    let is_wkhtmltopdf = Path::new(renderer)
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().contains("wkhtmltopdf"));
+    let mut command = Command::new(renderer);
+    if is_wkhtmltopdf {
+        command
+            .arg("--quiet")
+            .arg("--enable-local-file-access")
+            .arg(&html_path)
+            .arg(pdf_path);
+    } else {
+        command
+            .arg("--headless")
+            .arg("--disable-gpu")
+            .arg("--no-pdf-header-footer")
+            .arg(format!("--print-to-pdf={}", absolute(pdf_path).display()))
+            .arg(format!("file://{}", html_path.display()));
+    }
+
+    let output = command
+        .output()
+        .map_err(|e| format!("could not run {}: {}", renderer, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed ({}): {}",
+            renderer,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
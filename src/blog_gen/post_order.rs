@@ -0,0 +1,11 @@
+/// How the posts are ordered on the index (and everywhere else they're listed in order).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PostOrder {
+    /// by date, oldest first
+    #[default]
+    Date,
+
+    /// by the `order` in their front matter, lowest first, with the posts that have no
+    /// `order` coming after all of those that do, by date
+    Order,
+}
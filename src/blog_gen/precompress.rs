@@ -0,0 +1,38 @@
+use std::io::{self, Write};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// Which pre-compressed variants to write alongside each generated text file, for hosts
+/// that serve `.gz`/`.br` files directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Precompression {
+    Gzip,
+    Brotli,
+    Both,
+}
+
+impl Precompression {
+    pub fn gzip(&self) -> bool {
+        matches!(self, Precompression::Gzip | Precompression::Both)
+    }
+
+    pub fn brotli(&self) -> bool {
+        matches!(self, Precompression::Brotli | Precompression::Both)
+    }
+}
+
+pub fn gzip_compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+pub fn brotli_compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        encoder.write_all(bytes)?;
+    }
+    Ok(compressed)
+}
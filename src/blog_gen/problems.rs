@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Something wrong with a markdown source file that didn't stop the build.
+#[derive(Clone, Debug)]
+pub struct Problem {
+    /// the (approximate) line of the source file involved, counting from 1
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// The problems found over the course of a build, grouped by source file so they can
+/// be reported together once the build is done.
+#[derive(Clone, Debug, Default)]
+pub struct ProblemReport {
+    problems: BTreeMap<String, Vec<Problem>>,
+}
+
+impl ProblemReport {
+    pub fn add(&mut self, source: &str, line: Option<usize>, message: String) {
+        self.problems
+            .entry(source.to_string())
+            .or_default()
+            .push(Problem { line, message });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// the total number of problems, over all of the source files
+    pub fn len(&self) -> usize {
+        self.problems.values().map(Vec::len).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<Problem>)> {
+        self.problems.iter()
+    }
+}
+
+impl fmt::Display for ProblemReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "problems found: {}", self.len())?;
+        for (source, problems) in self.problems.iter() {
+            write!(f, "\n  {}", source)?;
+            for problem in problems {
+                match problem.line {
+                    Some(line) => write!(f, "\n    line {}: {}", line, problem.message)?,
+                    None => write!(f, "\n    {}", problem.message)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The line (counting from 1) of the first occurrence of `needle` in `text`.
+pub fn line_of(text: &str, needle: &str) -> Option<usize> {
+    let offset = text.find(needle)?;
+    Some(text[..offset].matches('\n').count() + 1)
+}
@@ -0,0 +1,39 @@
+use scraper::{ElementRef, Html, Selector};
+
+/// How many words a post has to go past to count as a long read, unless configured.
+pub const DEFAULT_LONG_READ_THRESHOLD: usize = 2000;
+
+/// The number of words in the text of the html, leaving out the markup and the table of
+/// contents (which only repeats the headings).
+pub fn word_count(html: &str) -> usize {
+    let fragment = Html::parse_fragment(html);
+    let toc = Selector::parse("nav.toc").unwrap();
+
+    let words = |element: ElementRef| -> usize {
+        element
+            .text()
+            .map(|text| text.split_whitespace().count())
+            .sum()
+    };
+    words(fragment.root_element()) - fragment.select(&toc).map(words).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_counted_without_the_markup() {
+        assert_eq!(
+            word_count("<h1>A title</h1>\n<p>Some <em>short</em> text.</p>\n"),
+            5
+        );
+    }
+
+    #[test]
+    fn the_table_of_contents_is_left_out() {
+        let html = "<h1 id=\"a\">A</h1>\n<nav class=\"toc\">\n<ul>\n<li><a href=\"#b\">Two words</a></li>\n\
+                    </ul>\n</nav>\n<h2 id=\"b\">Two words</h2>\n";
+        assert_eq!(word_count(html), 3);
+    }
+}
@@ -0,0 +1,42 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+
+/// A post as listed in the recent posts fragment.
+#[derive(Clone, Debug)]
+pub struct RecentPost {
+    pub title: String,
+    /// the absolute url of the post
+    pub url: String,
+    pub date: String,
+}
+
+/// The styles of the fragment, scoped to it so that they don't leak into the page it's
+/// embedded in.
+const RECENT_POSTS_STYLE: &str =
+    ".md-blog-recent { font-family: sans-serif; font-size: 14px; line-height: 1.4; }
+.md-blog-recent ul { list-style: none; margin: 0; padding: 0; }
+.md-blog-recent li { padding: 0.4em 0; border-bottom: 1px solid #ddd; }
+.md-blog-recent li:last-child { border-bottom: none; }
+.md-blog-recent a { color: #1a5fb4; text-decoration: none; }
+.md-blog-recent a:hover { text-decoration: underline; }
+.md-blog-recent .date { display: block; color: #777; font-size: 0.85em; }";
+
+/// A standalone html fragment listing the `posts` (most recent first), for embedding the
+/// latest posts of the blog in other sites with an `<iframe>` or a server side include.
+/// The fragment carries its own styles, and its links open in the embedding page's window.
+pub fn recent_posts_html(posts: &[RecentPost]) -> String {
+    let mut html = String::new();
+    html.push_str("<div class=\"md-blog-recent\">\n");
+    html.push_str(&format!("<style>\n{}\n</style>\n", RECENT_POSTS_STYLE));
+    html.push_str("<ul>\n");
+    for post in posts {
+        html.push_str(&format!(
+            "<li><a href=\"{}\" target=\"_top\">{}</a> <span class=\"date\">{}</span></li>\n",
+            encode_double_quoted_attribute(&post.url),
+            encode_text(&post.title),
+            encode_text(&post.date)
+        ));
+    }
+    html.push_str("</ul>\n</div>\n");
+
+    html
+}
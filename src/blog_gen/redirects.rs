@@ -0,0 +1,50 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+
+/// A page sending the browser on to `target_url`, written in place of a post's old path.
+/// The meta refresh does the work without javascript, and the canonical link tells
+/// crawlers where the post lives now.
+pub fn redirect_page(target_url: &str) -> String {
+    let attribute_url = encode_double_quoted_attribute(target_url);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Redirecting&hellip;</title>
+<link rel="canonical" href="{attribute_url}">
+<meta http-equiv="refresh" content="0; url={attribute_url}">
+<meta name="robots" content="noindex">
+<script>location.replace({script_url});</script>
+</head>
+<body>
+<p>This page has moved to <a href="{attribute_url}">{text_url}</a>.</p>
+</body>
+</html>
+"#,
+        attribute_url = attribute_url,
+        script_url = serde_json::to_string(target_url)
+            .unwrap()
+            .replace("</", "<\\/"),
+        text_url = encode_text(target_url),
+    )
+}
+
+/// A `_redirects` file (the format read by Netlify and Cloudflare Pages) with a permanent
+/// redirect for each `(from, to)` pair. `from` is a path from the root of the site.
+pub fn redirects_file(redirects: &[(String, String)]) -> String {
+    redirects
+        .iter()
+        .map(|(from, to)| format!("{} {} 301\n", from, to))
+        .collect()
+}
+
+/// The path of `path` from the root of the site, for a site served at `base_url`: an
+/// absolute base url contributes its own path (`https://example.com/blog/` gives `/blog/`).
+pub fn site_path(base_url: &str, path: &str) -> String {
+    let base_path = match base_url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+        None => "/",
+    };
+
+    format!("{}/{}", base_path.trim_end_matches('/'), path)
+}
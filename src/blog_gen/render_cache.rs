@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// The html rendered from a post's markdown, along with what was scraped out of it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RenderedMarkdown {
+    pub body_html: String,
+    pub title: Option<String>,
+    pub excerpt: String,
+}
+
+/// What a cached rendering depends on: the source file (its path and modified time), the
+/// hash of its markdown and the settings it was rendered with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub source: String,
+    pub modified: Option<u128>,
+    pub content_hash: String,
+    pub settings: String,
+}
+
+impl CacheKey {
+    pub fn new(
+        source: &str,
+        modified: Option<SystemTime>,
+        content_hash: String,
+        settings: String,
+    ) -> Self {
+        CacheKey {
+            source: source.to_string(),
+            modified: modified
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_nanos()),
+            content_hash,
+            settings,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: CacheKey,
+    rendered: RenderedMarkdown,
+}
+
+/// The rendered markdown of each post from earlier builds, so that rebuilding a post whose
+/// markdown hasn't changed can skip parsing it again. There's one entry per source file,
+/// replaced whenever the file changes.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RenderCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl RenderCache {
+    /// Reads a cache saved by `save`, starting an empty one if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(RenderCache::default());
+        }
+
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    /// The cached rendering for `key`, if the source hasn't changed since it was cached.
+    pub fn get(&self, key: &CacheKey) -> Option<&RenderedMarkdown> {
+        self.entries
+            .get(&key.source)
+            .filter(|entry| entry.key == *key)
+            .map(|entry| &entry.rendered)
+    }
+
+    pub fn insert(&mut self, key: CacheKey, rendered: RenderedMarkdown) {
+        self.entries
+            .insert(key.source.clone(), CacheEntry { key, rendered });
+    }
+}
@@ -0,0 +1,34 @@
+use ammonia::Builder;
+
+/// Strips anything that could run script from the rendered html of an untrusted post
+/// (`<script>`, `<style>`, event handlers, `javascript:` urls and the like), keeping
+/// the markup markdown itself produces: links, images, tables, code, footnotes and
+/// task lists, the video and audio of shortcodes, along with the ids and classes used for anchors and styling.
+pub fn sanitize_html(html: &str) -> String {
+    Builder::default()
+        .add_generic_attributes(["id", "class"])
+        .add_tags(["input", "picture", "source", "video", "audio"])
+        .add_tag_attributes("a", ["target", "rel"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("source", ["srcset", "type"])
+        .add_tag_attributes("video", ["src", "poster", "controls", "preload"])
+        .add_tag_attributes("audio", ["src", "controls", "preload"])
+        .add_tag_attributes("ol", ["start"])
+        .add_tag_attributes("th", ["align", "style"])
+        .add_tag_attributes("td", ["align", "style"])
+        .link_rel(None)
+        // tables carry their column alignment as inline styles, which are otherwise unsafe
+        .attribute_filter(|_, attribute, value| match attribute {
+            "style" if !is_text_align(value) => None,
+            _ => Some(value.into()),
+        })
+        .clean(html)
+        .to_string()
+}
+
+fn is_text_align(style: &str) -> bool {
+    matches!(
+        style.trim(),
+        "text-align: left" | "text-align: center" | "text-align: right"
+    )
+}
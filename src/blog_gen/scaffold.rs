@@ -0,0 +1,125 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config_file::CONFIG_FILE;
+
+const SAMPLE_POST: &str = "Hello World
+===========
+
+This is the first post of the blog. Every markdown file in `md_sources/` becomes a post,
+titled after its first heading, and the index links to all of them.
+
+## Writing posts
+
+Posts are written in markdown, with *emphasis*, [links](https://commonmark.org) and code:
+
+```rust
+fn main() {
+    println!(\"hello, world\");
+}
+```
+";
+
+const STARTER_STYLESHEET: &str = include_str!("../../css_sources/retro.css");
+
+const SAMPLE_CONFIG: &str =
+    "# The options md-blog-gen is run with in this dir, one per line as `name = value`
+# (or just `name` for a switch); see `md-blog-gen --help` for the rest of them. Options
+# given on the command line win over these.
+css-source = css_sources/style.css
+md-sources = md_sources
+rendered-outputs = rendered_html
+
+# a header with the site's title on every page
+# site-title = My Blog
+# an excerpt of each post on the index
+# index-excerpts
+# a table of contents at the top of each post
+# toc
+";
+
+/// What `scaffold_blog` created, and what it left alone because it was already there.
+#[derive(Clone, Debug, Default)]
+pub struct ScaffoldReport {
+    pub created: Vec<PathBuf>,
+    pub existing: Vec<PathBuf>,
+}
+
+/// Sets up a new blog in `dir`: a sample post in `md_sources/`, a starter stylesheet in
+/// `css_sources/`, an empty `rendered_html/` and a sample config (`md-blog-gen.conf`)
+/// pointing the generator at them, so that running it in `dir` builds the blog. Files and
+/// dirs that already exist are never overwritten.
+pub fn scaffold_blog(dir: &Path) -> Result<ScaffoldReport, String> {
+    let mut report = ScaffoldReport::default();
+
+    for sub_dir in ["", "md_sources", "css_sources", "rendered_html"] {
+        let path = dir.join(sub_dir);
+        if path.is_dir() {
+            if !sub_dir.is_empty() {
+                report.existing.push(path);
+            }
+            continue;
+        }
+        fs::create_dir_all(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        report.created.push(path);
+    }
+
+    let files = [
+        ("md_sources/hello_world.md", SAMPLE_POST),
+        ("css_sources/style.css", STARTER_STYLESHEET),
+        (CONFIG_FILE, SAMPLE_CONFIG),
+    ];
+    for (file, contents) in files {
+        let path = dir.join(file);
+        if path.exists() {
+            report.existing.push(path);
+            continue;
+        }
+        fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        report.created.push(path);
+    }
+
+    Ok(report)
+}
+
+impl fmt::Display for ScaffoldReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "created: {}", self.created.len())?;
+        for path in self.created.iter() {
+            write!(f, "\n  {}", path.display())?;
+        }
+        if !self.existing.is_empty() {
+            write!(f, "\nalready there, left as is: {}", self.existing.len())?;
+            for path in self.existing.iter() {
+                write!(f, "\n  {}", path.display())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaffolding_writes_a_config_and_leaves_existing_files_alone() {
+        let dir = std::env::temp_dir().join(format!("md-blog-gen-init-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let report = scaffold_blog(&dir).unwrap();
+        assert!(report.created.contains(&dir.join(CONFIG_FILE)));
+        assert!(report.existing.is_empty());
+        let config = fs::read_to_string(dir.join(CONFIG_FILE)).unwrap();
+        assert!(config.contains("md-sources = md_sources"));
+
+        fs::write(dir.join(CONFIG_FILE), "toc\n").unwrap();
+        let report = scaffold_blog(&dir).unwrap();
+        assert!(report.created.is_empty());
+        assert_eq!(fs::read_to_string(dir.join(CONFIG_FILE)).unwrap(), "toc\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use regex::{Captures, Regex};
+
+/// Expands the shortcodes in a post's markdown into the html they stand for, returning the
+/// expanded markdown along with the shortcodes that couldn't be expanded (which are left
+/// as they are) and why not. Shortcodes inside fenced code blocks are left alone.
+///
+/// ```text
+/// {{< video src="clip.mp4" poster="clip.jpg" >}}
+/// {{< audio src="episode.mp3" >}}
+/// ```
+pub fn expand_shortcodes(md_content: &str) -> (String, Vec<(String, &'static str)>) {
+    let shortcode = Regex::new(r#"\{\{<\s*(\w+)((?:\s+\w+\s*=\s*"[^"]*")*)\s*>\}\}"#).unwrap();
+
+    let mut unexpanded: Vec<(String, &'static str)> = Vec::new();
+    let mut in_fence = false;
+    let mut expanded = String::with_capacity(md_content.len());
+
+    for line in md_content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            expanded.push_str(line);
+            continue;
+        }
+
+        let line = shortcode.replace_all(line, |caps: &Captures| {
+            let args = shortcode_args(&caps[2]);
+            match shortcode_html(&caps[1], &args) {
+                Ok(html) => html,
+                Err(problem) => {
+                    unexpanded.push((caps[0].to_string(), problem));
+                    caps[0].to_string()
+                }
+            }
+        });
+        expanded.push_str(&line);
+    }
+
+    (expanded, unexpanded)
+}
+
+fn shortcode_args(args: &str) -> BTreeMap<String, String> {
+    let arg = Regex::new(r#"(\w+)\s*=\s*"([^"]*)""#).unwrap();
+    arg.captures_iter(args)
+        .map(|caps| (caps[1].to_string(), caps[2].to_string()))
+        .collect()
+}
+
+fn shortcode_html(name: &str, args: &BTreeMap<String, String>) -> Result<String, &'static str> {
+    if !matches!(name, "video" | "audio") {
+        return Err("unknown shortcode");
+    }
+    let src = args.get("src").ok_or("shortcode without a src")?;
+    match name {
+        "video" => Ok(video_html(src, args.get("poster").map(String::as_str))),
+        _ => Ok(audio_html(src)),
+    }
+}
+
+/// Whether a piece of raw html is exactly what a video or audio shortcode expands to, so
+/// that it can be kept when the rest of the raw html in the posts is dropped.
+pub fn is_shortcode_html(html: &str) -> bool {
+    let shortcode_html = Regex::new(
+        r#"^<figure class="(video|audio)"><(video|audio) controls src="[^"<>]*"(?: poster="[^"<>]*")? preload="metadata"></(video|audio)></figure>\n?$"#,
+    )
+    .unwrap();
+    shortcode_html
+        .captures(html)
+        .is_some_and(|caps| caps[1] == caps[2] && caps[2] == caps[3])
+}
+
+/// A `<video>` with the browser's controls, in a figure so that it stands as a block of
+/// its own in the markdown.
+pub fn video_html(src: &str, poster: Option<&str>) -> String {
+    let poster = poster
+        .map(|poster| {
+            format!(
+                " poster=\"{}\"",
+                html_escape::encode_double_quoted_attribute(poster)
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        "<figure class=\"video\"><video controls src=\"{}\"{} preload=\"metadata\"></video></figure>",
+        html_escape::encode_double_quoted_attribute(src),
+        poster
+    )
+}
+
+/// An `<audio>` with the browser's controls, in a figure like `video_html`.
+pub fn audio_html(src: &str) -> String {
+    format!(
+        "<figure class=\"audio\"><audio controls src=\"{}\" preload=\"metadata\"></audio></figure>",
+        html_escape::encode_double_quoted_attribute(src)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_a_video() {
+        let (expanded, unexpanded) = expand_shortcodes("{{< video src=\"clip.mp4\" >}}\n");
+        assert!(expanded.contains("<video controls src=\"clip.mp4\""));
+        assert!(unexpanded.is_empty());
+    }
+
+    #[test]
+    fn recognizes_its_own_html() {
+        assert!(is_shortcode_html(&video_html("clip.mp4", Some("clip.jpg"))));
+        assert!(is_shortcode_html(&format!(
+            "{}\n",
+            audio_html("episode.mp3")
+        )));
+        assert!(!is_shortcode_html(
+            "<figure class=\"video\"><video controls src=\"a.mp4\" onplay=\"x()\" preload=\"metadata\"></video></figure>"
+        ));
+        assert!(!is_shortcode_html("<div>hi</div>"));
+    }
+
+    #[test]
+    fn reports_a_missing_src() {
+        let (_, unexpanded) = expand_shortcodes("{{< audio >}}\n");
+        assert_eq!(
+            unexpanded,
+            vec![("{{< audio >}}".to_string(), "shortcode without a src")]
+        );
+    }
+
+    #[test]
+    fn reports_an_unknown_shortcode() {
+        let (_, unexpanded) = expand_shortcodes("{{< gallery src=\"a.jpg\" >}}\n");
+        assert_eq!(unexpanded[0].1, "unknown shortcode");
+    }
+}
@@ -0,0 +1,59 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// How far back the posts of a build go: from a date on, or only as far back as some time
+/// before the build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Since {
+    /// posts dated on or after this time
+    Date(DateTime<Utc>),
+
+    /// posts dated within this long before the build
+    Ago(Duration),
+}
+
+impl Since {
+    /// The earliest date of a post in a build at `build_time`.
+    pub fn cutoff(&self, build_time: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Since::Date(date) => *date,
+            Since::Ago(duration) => build_time - *duration,
+        }
+    }
+}
+
+impl FromStr for Since {
+    type Err = String;
+
+    /// Parses a `YYYY-MM-DD` date (taken as midnight UTC), an RFC 3339 timestamp, or a
+    /// number of hours, days or weeks back, like `12h`, `7d` or `2w`.
+    fn from_str(since: &str) -> Result<Self, Self::Err> {
+        if let Ok(date_time) = DateTime::parse_from_rfc3339(since) {
+            return Ok(Since::Date(date_time.with_timezone(&Utc)));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+            return Ok(Since::Date(date.and_hms_opt(0, 0, 0).unwrap().and_utc()));
+        }
+
+        let invalid = || {
+            format!(
+                "{:?} is not a YYYY-MM-DD date, an RFC 3339 timestamp or a time back like 7d",
+                since
+            )
+        };
+        let (unit_start, _) = since.char_indices().last().ok_or_else(invalid)?;
+        let count = since[..unit_start]
+            .parse::<u32>()
+            .map(i64::from)
+            .map_err(|_| invalid())?;
+        let duration = match &since[unit_start..] {
+            "h" => Duration::try_hours(count),
+            "d" => Duration::try_days(count),
+            "w" => Duration::try_weeks(count),
+            _ => None,
+        };
+
+        duration.map(Since::Ago).ok_or_else(invalid)
+    }
+}
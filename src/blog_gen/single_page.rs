@@ -0,0 +1,49 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use regex::{Captures, Regex};
+
+/// A post as one section of the single page holding all of them.
+#[derive(Clone, Debug)]
+pub struct SinglePagePost {
+    /// the id of the post's section, which its own ids are prefixed with
+    pub id: String,
+    pub title: String,
+    pub date: String,
+    pub html: String,
+}
+
+/// Prefixes every id in the html (and the fragment links to them) with `prefix`, so that
+/// the headings and footnotes of posts put on the same page can't collide.
+pub fn prefix_ids(html: &str, prefix: &str) -> String {
+    let id = Regex::new(r##"(\s(?:id="|href="#))([^"]*)""##).unwrap();
+    id.replace_all(html, |caps: &Captures| {
+        format!("{}{}-{}\"", &caps[1], prefix, &caps[2])
+    })
+    .into_owned()
+}
+
+/// The body of the page holding all of the `posts`, in order: a table of contents linking
+/// to each of them, followed by a `<section>` for each.
+pub fn single_page_html(heading: &str, posts: &[SinglePagePost]) -> String {
+    let mut html = String::new();
+    html.push_str("<nav class=\"single-page-toc\">\n");
+    html.push_str(&format!("<h1>{}</h1>\n<ol>\n", encode_text(heading)));
+    for post in posts {
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a> <span class=\"date\">{}</span></li>\n",
+            encode_double_quoted_attribute(&post.id),
+            encode_text(&post.title),
+            encode_text(&post.date)
+        ));
+    }
+    html.push_str("</ol>\n</nav>\n");
+
+    for post in posts {
+        html.push_str(&format!(
+            "<section id=\"{}\">\n{}</section>\n",
+            encode_double_quoted_attribute(&post.id),
+            post.html
+        ));
+    }
+
+    html
+}
@@ -0,0 +1,31 @@
+use serde::Serialize;
+use tera::{Context, Tera};
+
+/// The site-wide variables available to every template as `site`:
+///
+/// - `site.title`: the title given with `--site-title`, if there is one
+/// - `site.base_url`: the url the site is served from
+/// - `site.build_time`: when the site was built, as an RFC 3339 timestamp
+/// - `site.post_count`: how many posts the build publishes
+#[derive(Clone, Debug, Serialize)]
+pub struct Site {
+    pub title: Option<String>,
+    pub base_url: String,
+    pub build_time: String,
+    pub post_count: usize,
+}
+
+/// The templates of a build, which are rendered with the `site` variables added to
+/// whatever context they're given.
+pub struct SiteTemplates {
+    pub templates: Tera,
+    pub site: Site,
+}
+
+impl SiteTemplates {
+    pub fn render(&self, name: &str, context: &Context) -> tera::Result<String> {
+        let mut context = context.clone();
+        context.insert("site", &self.site);
+        self.templates.render(name, &context)
+    }
+}
@@ -0,0 +1,92 @@
+use deunicode::deunicode_char;
+use sha2::{Digest, Sha256};
+
+/// Turns a piece of text (a title, a heading, a tag) into a lowercase, dash separated
+/// string suitable for use in a url or as an html id.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_dash = false;
+
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.push(c);
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            pending_dash = true;
+        }
+    }
+
+    slug
+}
+
+/// Like `slugify`, but with the text transliterated to plain ASCII (with deunicode), so that
+/// the slug needs no percent-encoding in a url: `Café Résumé` becomes `cafe-resume` and
+/// `Привет мир` becomes `privet-mir`. Characters that can't be transliterated are left
+/// out, and a short hash of the text is added on the end in their place, so that texts
+/// differing only in them still get different slugs.
+pub fn transliterated_slugify(text: &str) -> String {
+    let mut untransliterated = false;
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match deunicode_char(c) {
+            Some(latin) => ascii.push_str(latin),
+            None => {
+                untransliterated = true;
+                ascii.push(' ');
+            }
+        }
+    }
+
+    let mut slug = slugify(&ascii);
+    if untransliterated {
+        let hash: String = Sha256::digest(text.as_bytes())
+            .iter()
+            .take(4)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&hash);
+    }
+
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_are_lowercase_and_dash_separated() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(
+            slugify("  snake_case - and  spaces "),
+            "snake-case-and-spaces"
+        );
+        assert_eq!(slugify("Café"), "café");
+    }
+
+    #[test]
+    fn accented_titles_are_transliterated() {
+        assert_eq!(transliterated_slugify("Café Résumé"), "cafe-resume");
+        assert_eq!(transliterated_slugify("Straße in Łódź"), "strasse-in-lodz");
+    }
+
+    #[test]
+    fn non_latin_titles_are_transliterated() {
+        assert_eq!(transliterated_slugify("Привет мир"), "privet-mir");
+        assert_eq!(transliterated_slugify("Γειά σου"), "geia-sou");
+        assert_eq!(transliterated_slugify("北京"), "bei-jing");
+    }
+
+    #[test]
+    fn untransliterated_characters_are_hashed() {
+        let slug = transliterated_slugify("notes \u{e000}");
+        assert!(slug.starts_with("notes-") && slug.len() == "notes-".len() + 8);
+        assert_ne!(slug, transliterated_slugify("notes \u{e001}"));
+    }
+}
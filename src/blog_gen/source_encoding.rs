@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use encoding_rs::Encoding;
+
+/// The encoding that markdown sources which aren't valid UTF-8 are decoded from, for older
+/// posts written before everything was UTF-8. Any of the WHATWG encoding labels names one
+/// (`latin1`, `windows-1252`, `shift_jis`, `koi8-r` and so on).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceEncoding(&'static Encoding);
+
+impl SourceEncoding {
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        self.0.decode_without_bom_handling(bytes).0.into_owned()
+    }
+}
+
+impl FromStr for SourceEncoding {
+    type Err = String;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        Encoding::for_label(label.as_bytes())
+            .map(SourceEncoding)
+            .ok_or_else(|| format!("{:?} isn't the label of an encoding", label))
+    }
+}
+
+/// Reads a markdown source file as UTF-8 (leaving out any byte order mark), or decodes it
+/// from the `fallback` encoding if it isn't valid UTF-8. Without a fallback, a file that
+/// isn't valid UTF-8 is an error saying where its first invalid byte is.
+pub fn read_source(path: &Path, fallback: Option<SourceEncoding>) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+
+    match String::from_utf8(bytes) {
+        Ok(source) => Ok(source
+            .strip_prefix('\u{feff}')
+            .map(str::to_string)
+            .unwrap_or(source)),
+        Err(e) => match fallback {
+            Some(encoding) => Ok(encoding.decode(e.as_bytes())),
+            None => Err(format!(
+                "it isn't valid UTF-8 (the first invalid byte is at offset {}); convert it \
+                 to UTF-8, or give the encoding it's in",
+                e.utf8_error().valid_up_to()
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin1_sources_are_decoded() {
+        let encoding: SourceEncoding = "latin1".parse().unwrap();
+        assert_eq!(encoding.decode(b"caf\xe9 \xabna\xefve\xbb"), "café «naïve»");
+        // "Latin-1" files are really Windows-1252, as the labels say
+        assert_eq!(encoding.decode(b"\x93quoted\x94"), "“quoted”");
+        assert_eq!(encoding, "ISO-8859-1".parse().unwrap());
+    }
+
+    #[test]
+    fn other_encodings_are_decoded() {
+        let encoding: SourceEncoding = "koi8-r".parse().unwrap();
+        assert_eq!(encoding.decode(b"\xf0\xd2\xc9\xd7\xc5\xd4"), "Привет");
+        assert!("no-such-encoding".parse::<SourceEncoding>().is_err());
+    }
+
+    #[test]
+    fn utf8_sources_lose_their_byte_order_mark() {
+        let dir = std::env::temp_dir().join(format!("md-blog-gen-encoding-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.md");
+        fs::write(&path, b"\xef\xbb\xbf# Hi\n").unwrap();
+        assert_eq!(read_source(&path, None).unwrap(), "# Hi\n");
+        fs::write(&path, b"# caf\xe9\n").unwrap();
+        assert!(read_source(&path, None).is_err());
+        assert_eq!(
+            read_source(&path, "latin1".parse().ok()).unwrap(),
+            "# café\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
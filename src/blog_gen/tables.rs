@@ -0,0 +1,37 @@
+use regex::Regex;
+
+/// Adds an `align-left`, `align-center` or `align-right` class to each table cell that
+/// pulldown-cmark gave a `text-align` style (from the `:---`, `:---:` and `---:` of the
+/// markdown), so that themes can style the alignment, and it still applies when inline
+/// styles are blocked by a Content-Security-Policy.
+pub fn add_alignment_classes(html: &str) -> String {
+    // the cells are matched as markup, since a lone <td> doesn't parse outside of a table
+    let cell = Regex::new(r#"<(th|td) style="text-align: (left|center|right)">"#).unwrap();
+    cell.replace_all(html, r#"<$1 style="text-align: $2" class="align-$2">"#)
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::{html, Options, Parser};
+
+    use super::*;
+
+    #[test]
+    fn right_aligned_columns_get_the_class() {
+        let mut table = String::new();
+        let md = "| a | b |\n| --- | ---: |\n| 1 | 2 |\n";
+        html::push_html(&mut table, Parser::new_ext(md, Options::ENABLE_TABLES));
+        let table = add_alignment_classes(&table);
+        assert!(table.contains("<th>a</th>"));
+        assert!(table.contains("<td>1</td>"));
+        assert!(table.contains(r#"<th style="text-align: right" class="align-right">b</th>"#));
+        assert!(table.contains(r#"<td style="text-align: right" class="align-right">2</td>"#));
+    }
+
+    #[test]
+    fn aligned_cells_are_matched_only_as_markup() {
+        let html = r#"<p>&lt;td style="text-align: left"&gt;</p>"#;
+        assert_eq!(add_alignment_classes(html), html);
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// The number of font size steps used in the tag cloud.
+const TAG_CLOUD_WEIGHTS: usize = 5;
+
+/// A tag as shown in the tag cloud on the index page.
+#[derive(Clone, Debug, Serialize)]
+pub struct TagCloudEntry {
+    pub name: String,
+    pub url: String,
+    pub count: usize,
+
+    /// 1 (the least used tags) to 5 (the most used), for picking a font size
+    pub weight: usize,
+}
+
+/// Builds the tag cloud from the posts carrying each tag, scaling the counts linearly
+/// between the least and most used tags. `url` gives the tag page of each tag.
+pub fn tag_cloud<F>(tag_posts: &BTreeMap<String, Vec<String>>, url: F) -> Vec<TagCloudEntry>
+where
+    F: Fn(&str) -> String,
+{
+    let min_count = tag_posts.values().map(Vec::len).min().unwrap_or(0);
+    let max_count = tag_posts.values().map(Vec::len).max().unwrap_or(0);
+
+    tag_posts
+        .iter()
+        .map(|(name, posts)| {
+            let weight = if max_count == min_count {
+                1
+            } else {
+                1 + (posts.len() - min_count) * (TAG_CLOUD_WEIGHTS - 1) / (max_count - min_count)
+            };
+
+            TagCloudEntry {
+                name: name.clone(),
+                url: url(name),
+                count: posts.len(),
+                weight,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_cloud_weights_scale_with_the_counts() {
+        let tag_posts: BTreeMap<String, Vec<String>> = [
+            ("a", vec!["1.html"]),
+            ("b", vec!["1.html", "2.html", "3.html", "4.html", "5.html"]),
+            ("c", vec!["1.html", "2.html", "3.html"]),
+        ]
+        .into_iter()
+        .map(|(tag, posts)| {
+            (
+                tag.to_string(),
+                posts.into_iter().map(String::from).collect(),
+            )
+        })
+        .collect();
+
+        let cloud = tag_cloud(&tag_posts, |tag| format!("tags/{}.html", tag));
+        let weights: Vec<_> = cloud
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.weight))
+            .collect();
+        assert_eq!(weights, vec![("a", 1), ("b", 5), ("c", 3)]);
+        assert_eq!(cloud[1].url, "tags/b.html");
+    }
+
+    #[test]
+    fn tags_used_equally_get_the_least_weight() {
+        let tag_posts: BTreeMap<String, Vec<String>> =
+            [("a".to_string(), vec!["1.html".to_string()])]
+                .into_iter()
+                .collect();
+        assert_eq!(tag_cloud(&tag_posts, |tag| tag.to_string())[0].weight, 1);
+    }
+}
@@ -0,0 +1,46 @@
+use std::str::FromStr;
+
+use glob::Pattern;
+
+/// A rule picking the post template of the posts whose file names match a glob pattern,
+/// written as `PATTERN=FILE`, like `link-*.md=templates/link.html`.
+#[derive(Clone, Debug)]
+pub struct TemplateRule {
+    pub pattern: Pattern,
+    /// the tera template file used for the matching posts
+    pub template: String,
+}
+
+impl FromStr for TemplateRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let (pattern, template) = rule
+            .split_once('=')
+            .filter(|(pattern, template)| !pattern.is_empty() && !template.is_empty())
+            .ok_or_else(|| format!("{:?} is not a template rule like link-*.md=link.html", rule))?;
+        let pattern = Pattern::new(pattern)
+            .map_err(|e| format!("{:?} is not a valid glob pattern: {}", pattern, e))?;
+
+        Ok(TemplateRule {
+            pattern,
+            template: template.to_string(),
+        })
+    }
+}
+
+/// The template of the first of the `rules` matching the file name of a post, if any do.
+/// Rules are tried in the order they're given, so a more specific pattern has to come
+/// before a more general one to win over it.
+pub fn template_for<'a>(rules: &'a [TemplateRule], file_name: &str) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|rule| rule.pattern.matches(file_name))
+        .map(|rule| rule.template.as_str())
+}
+
+/// The name a rule's template is added to tera under, which (like the post template's
+/// `"html"`) doesn't end in `.html`, so that tera doesn't escape the post's html in it.
+pub fn rule_template_name(template: &str) -> String {
+    format!("html[{}]", template)
+}
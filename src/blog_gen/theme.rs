@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+
+use super::blog_generator::BlogGeneratorError;
+
+/// A directory bundling a theme's stylesheet, templates and static files:
+///
+/// - `style.css`: the stylesheet inlined into every post (required)
+/// - `post.html`: the tera template for posts (required)
+/// - `index.html`: the tera template for the index page (optional)
+/// - `static/`: files copied as is into the rendered outputs dir (optional)
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub css_source_file: PathBuf,
+    pub post_template_file: PathBuf,
+    pub index_template_file: Option<PathBuf>,
+    pub static_dir: Option<PathBuf>,
+}
+
+impl Theme {
+    pub fn load(theme_dir: &str) -> Result<Self, BlogGeneratorError> {
+        let dir = Path::new(theme_dir);
+        if !dir.is_dir() {
+            return Err(BlogGeneratorError::InvalidTheme(
+                theme_dir.to_string(),
+                "not a directory".to_string(),
+            ));
+        }
+
+        let css_source_file = dir.join("style.css");
+        if !css_source_file.is_file() {
+            return Err(BlogGeneratorError::InvalidTheme(
+                theme_dir.to_string(),
+                "missing the style.css stylesheet".to_string(),
+            ));
+        }
+
+        let post_template_file = dir.join("post.html");
+        if !post_template_file.is_file() {
+            return Err(BlogGeneratorError::InvalidTheme(
+                theme_dir.to_string(),
+                "missing the post.html template".to_string(),
+            ));
+        }
+
+        let index_template_file = Some(dir.join("index.html")).filter(|p| p.is_file());
+        let static_dir = Some(dir.join("static")).filter(|p| p.is_dir());
+
+        Ok(Theme {
+            css_source_file,
+            post_template_file,
+            index_template_file,
+            static_dir,
+        })
+    }
+}
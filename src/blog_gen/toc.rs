@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag};
+
+use super::heading_numbers::heading_numbers;
+use super::slug::{slugify, transliterated_slugify};
+
+struct TocEntry {
+    level: HeadingLevel,
+    id: String,
+    text: String,
+    /// the section number of the heading, when the headings are numbered
+    number: Option<String>,
+    /// the heading is left out of the table of contents, though it still gets its id
+    excluded: bool,
+}
+
+/// Gives every heading in the event stream an id, and inserts a table of contents
+/// linking to them right after the post title (the first `title_level` heading).
+///
+/// Only headings below the title level, down to and including `max_depth`, are listed,
+/// leaving out headings with a `no-toc` class or whose text is one of `exclude`.
+/// Skipped levels (an `<h2>` followed directly by an `<h4>`) nest a single level deeper,
+/// so that the generated lists stay valid. With `numbered` headings, the entries are
+/// numbered the same way. The ids of the headings are `transliterated` to ASCII when set.
+pub fn add_table_of_contents<'a>(
+    events: Vec<Event<'a>>,
+    title_level: HeadingLevel,
+    max_depth: HeadingLevel,
+    exclude: &[String],
+    numbered: bool,
+    transliterated: bool,
+) -> Vec<Event<'a>> {
+    let mut entries = collect_headings(&events, exclude, transliterated);
+    if numbered {
+        let levels: Vec<HeadingLevel> = entries.iter().map(|e| e.level).collect();
+        for (entry, number) in entries
+            .iter_mut()
+            .zip(heading_numbers(&levels, title_level))
+        {
+            entry.number = number;
+        }
+    }
+
+    let toc_entries: Vec<&TocEntry> = entries
+        .iter()
+        .filter(|e| e.level > title_level && e.level <= max_depth && !e.excluded)
+        .collect();
+    let toc_html = render_toc(&toc_entries);
+
+    let mut output: Vec<Event> = Vec::with_capacity(events.len() + 1);
+    let mut headings = entries.iter();
+    let mut toc_inserted = toc_html.is_empty();
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading(level, _, classes)) => {
+                let entry = headings.next().unwrap();
+                output.push(Event::Html(CowStr::from(heading_open_tag(
+                    level, &entry.id, &classes,
+                ))));
+            }
+            Event::End(Tag::Heading(level, _, _)) => {
+                output.push(Event::Html(CowStr::from(format!("</{}>\n", level))));
+                if !toc_inserted && level == title_level {
+                    output.push(Event::Html(CowStr::from(toc_html.clone())));
+                    toc_inserted = true;
+                }
+            }
+            _ => output.push(event),
+        }
+    }
+
+    // a post without a title still gets its table of contents, at the top
+    if !toc_inserted {
+        output.insert(0, Event::Html(CowStr::from(toc_html)));
+    }
+
+    output
+}
+
+fn collect_headings(
+    events: &[Event<'_>],
+    exclude: &[String],
+    transliterated: bool,
+) -> Vec<TocEntry> {
+    let mut entries: Vec<TocEntry> = Vec::new();
+    let mut used_ids: HashSet<String> = HashSet::new();
+    let mut current: Option<(HeadingLevel, Option<String>, bool, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading(level, id, classes)) => {
+                let no_toc = classes.contains(&"no-toc");
+                current = Some((*level, id.map(str::to_string), no_toc, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, _, heading_text)) = current.as_mut() {
+                    heading_text.push_str(text);
+                }
+            }
+            Event::End(Tag::Heading(..)) => {
+                if let Some((level, explicit_id, no_toc, text)) = current.take() {
+                    let slug = || match transliterated {
+                        true => transliterated_slugify(&text),
+                        false => slugify(&text),
+                    };
+                    let id = unique_id(explicit_id.unwrap_or_else(slug), &used_ids);
+                    used_ids.insert(id.clone());
+                    let excluded = no_toc || exclude.iter().any(|e| e.trim() == text.trim());
+                    entries.push(TocEntry {
+                        level,
+                        id,
+                        text,
+                        number: None,
+                        excluded,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+fn unique_id(base: String, used_ids: &HashSet<String>) -> String {
+    let base = if base.is_empty() {
+        "section".to_string()
+    } else {
+        base
+    };
+
+    let mut id = base.clone();
+    let mut suffix = 1;
+    while used_ids.contains(&id) {
+        id = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+
+    id
+}
+
+fn heading_open_tag(level: HeadingLevel, id: &str, classes: &[&str]) -> String {
+    let id = encode_double_quoted_attribute(id);
+    if classes.is_empty() {
+        format!("<{} id=\"{}\">", level, id)
+    } else {
+        format!(
+            "<{} id=\"{}\" class=\"{}\">",
+            level,
+            id,
+            encode_double_quoted_attribute(&classes.join(" "))
+        )
+    }
+}
+
+fn render_toc(entries: &[&TocEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let mut html = String::from("<nav class=\"toc\">\n");
+    let mut open_levels: Vec<HeadingLevel> = Vec::new();
+
+    for entry in entries {
+        match open_levels.last().copied() {
+            None => {
+                html.push_str("<ul>\n");
+                open_levels.push(entry.level);
+            }
+            Some(_) => {
+                // close nested lists until the entry fits below its parent list, so that
+                // an entry shallower than a skipped-to level joins that list instead
+                while open_levels.len() > 1 && entry.level <= open_levels[open_levels.len() - 2] {
+                    html.push_str("</li>\n</ul>\n");
+                    open_levels.pop();
+                }
+
+                if entry.level > *open_levels.last().unwrap() {
+                    html.push_str("\n<ul>\n");
+                    open_levels.push(entry.level);
+                } else {
+                    html.push_str("</li>\n");
+                }
+            }
+        }
+
+        let number = entry
+            .number
+            .as_ref()
+            .map(|number| format!("<span class=\"heading-number\">{}</span> ", number))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}{}</a>",
+            encode_double_quoted_attribute(&entry.id),
+            number,
+            encode_text(&entry.text)
+        ));
+    }
+
+    for _ in open_levels {
+        html.push_str("</li>\n</ul>\n");
+    }
+    html.push_str("</nav>\n");
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::{html, Options, Parser};
+
+    use super::*;
+
+    fn with_toc(md: &str, max_depth: HeadingLevel) -> String {
+        with_toc_excluding(md, max_depth, &[])
+    }
+
+    fn with_toc_excluding(md: &str, max_depth: HeadingLevel, exclude: &[String]) -> String {
+        let events = Parser::new_ext(md, Options::ENABLE_HEADING_ATTRIBUTES).collect();
+        let events =
+            add_table_of_contents(events, HeadingLevel::H1, max_depth, exclude, false, false);
+        let mut body = String::new();
+        html::push_html(&mut body, events.into_iter());
+        body
+    }
+
+    #[test]
+    fn headings_deeper_than_the_depth_are_left_out() {
+        let body = with_toc(
+            "# Title\n\n## Two\n\n### Three\n\n#### Four\n",
+            HeadingLevel::H3,
+        );
+        assert!(body.starts_with(
+            "<h1 id=\"title\">Title</h1>\n<nav class=\"toc\">\n<ul>\n<li><a href=\"#two\">Two</a>\n\
+             <ul>\n<li><a href=\"#three\">Three</a></li>\n</ul>\n</li>\n</ul>\n</nav>\n"
+        ));
+        // though they still get their ids
+        assert!(body.contains("<h4 id=\"four\">Four</h4>"));
+
+        let body = with_toc("# Title\n\n## Two\n\n### Three\n", HeadingLevel::H2);
+        assert!(body.contains("<li><a href=\"#two\">Two</a></li>"));
+        assert!(!body.contains("href=\"#three\""));
+    }
+
+    #[test]
+    fn no_table_of_contents_without_headings_below_the_title() {
+        assert_eq!(
+            with_toc("# Title\n\ntext\n", HeadingLevel::H3),
+            "<h1 id=\"title\">Title</h1>\n<p>text</p>\n"
+        );
+    }
+
+    #[test]
+    fn excluded_headings_are_left_out_but_keep_their_ids() {
+        let body = with_toc_excluding(
+            "# Title\n\n## Intro\n\n## Notes {.no-toc}\n\n## Comments\n",
+            HeadingLevel::H3,
+            &["Comments".to_string()],
+        );
+        assert!(body.contains("<li><a href=\"#intro\">Intro</a></li>"));
+        assert!(!body.contains("href=\"#notes\""));
+        assert!(!body.contains("href=\"#comments\""));
+        assert!(body.contains("<h2 id=\"notes\" class=\"no-toc\">Notes</h2>"));
+        assert!(body.contains("<h2 id=\"comments\">Comments</h2>"));
+    }
+}
@@ -0,0 +1,18 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// The modified time of each of `paths` (`None` for a file that's gone), so that a later
+/// snapshot can be compared against it to see whether anything has changed.
+pub fn modified_times(paths: &[PathBuf]) -> BTreeMap<PathBuf, Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            let modified = fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (path.clone(), modified)
+        })
+        .collect()
+}
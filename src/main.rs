@@ -1,23 +1,833 @@
-use anyhow::Result;
-use clap::Parser;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
-use md_blog_gen::blog_gen::blog_generator::BlogGenerator;
+use anyhow::{anyhow, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::DateTime;
+use clap::{CommandFactory, Parser};
+
+use md_blog_gen::blog_gen::archive_layout::ArchiveLayout;
+use md_blog_gen::blog_gen::blog_generator::{
+    BlogGenerator, DEFAULT_MAX_FILE_SIZE, MAX_WRITE_RETRIES,
+};
+use md_blog_gen::blog_gen::blogroll::Blogroll;
+use md_blog_gen::blog_gen::config_file::{parse_config, CONFIG_FILE};
+use md_blog_gen::blog_gen::feed::{FeedContent, FeedFormat};
+use md_blog_gen::blog_gen::markdown_flavor::MarkdownFlavor;
+use md_blog_gen::blog_gen::output_format::LineEnding;
+use md_blog_gen::blog_gen::pdf::find_pdf_renderer;
+use md_blog_gen::blog_gen::post_order::PostOrder;
+use md_blog_gen::blog_gen::precompress::Precompression;
+use md_blog_gen::blog_gen::reading::DEFAULT_LONG_READ_THRESHOLD;
+use md_blog_gen::blog_gen::scaffold::scaffold_blog;
+use md_blog_gen::blog_gen::since::Since;
+use md_blog_gen::blog_gen::source_encoding::SourceEncoding;
+use md_blog_gen::blog_gen::template_rules::TemplateRule;
+use md_blog_gen::blog_gen::theme::Theme;
+use md_blog_gen::blog_gen::watch::modified_times;
+use pulldown_cmark::Options;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about=None)]
 struct Args {
-    #[arg(short, long, help = "path to the CSS source file")]
-    css_source: String,
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["theme", "init"],
+        help = "path to the CSS source file; given more than once (as name=path or just path), the site is rendered for each into a subfolder named after it"
+    )]
+    css_source: Vec<String>,
 
-    #[arg(short, long, help = "path to the dir containing the markdown files")]
-    md_sources: String,
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["stdin", "bundle", "init"],
+        help = "path to the dir containing the markdown files"
+    )]
+    md_sources: Option<String>,
 
     #[arg(
         short,
         long,
+        required_unless_present_any = ["stdin", "init"],
         help = "path to the dir into which the rendered files will be written"
     )]
-    rendered_outputs: String,
+    rendered_outputs: Option<String>,
+
+    #[arg(
+        long,
+        help = "render markdown read from stdin and write the html to stdout"
+    )]
+    stdin: bool,
+
+    #[arg(
+        long,
+        help = "write only the rendered post fragments, without the html template or index"
+    )]
+    bare: bool,
+
+    #[arg(
+        long,
+        requires = "bare",
+        help = "still generate the index page when running in bare mode"
+    )]
+    keep_index: bool,
+
+    #[arg(long, help = "treat warnings (such as duplicate titles) as errors")]
+    strict: bool,
+
+    #[arg(
+        long,
+        help = "inline local @import statements in the css source (relative to the css file)"
+    )]
+    resolve_css_imports: bool,
+
+    #[arg(long, help = "warn about images that have no alt text")]
+    check_alt: bool,
+
+    #[arg(
+        long,
+        help = "warn about posts whose headings skip a level, or that don't have exactly one title heading"
+    )]
+    check_headings: bool,
+
+    #[arg(long, help = "also generate a 404.html page")]
+    not_found_page: bool,
+
+    #[arg(
+        long,
+        help = "path to a markdown file to use as the content of the 404.html page"
+    )]
+    not_found_source: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "shift all heading levels down by this amount (clamped at h6)"
+    )]
+    heading_offset: usize,
+
+    #[arg(
+        long,
+        help = "show an excerpt of each post on the index (the text above a <!-- more --> marker, or the first paragraph)"
+    )]
+    index_excerpts: bool,
+
+    #[arg(
+        long,
+        help = "copy local images referenced by the posts into the rendered outputs dir"
+    )]
+    copy_images: bool,
+
+    #[arg(
+        long,
+        help = "copy local images and also generate webp versions of them, served via <picture>"
+    )]
+    optimize_images: bool,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "embed local images no bigger than this many bytes in the posts as data: uris"
+    )]
+    inline_small_images: Option<u64>,
+
+    #[arg(long, help = "add a table of contents to each post")]
+    toc: bool,
+
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "the deepest heading level (1-6) included in the table of contents"
+    )]
+    toc_depth: usize,
+
+    #[arg(
+        long,
+        help = "number the headings below each post's title (1, 1.1, 1.2, 2, ...), in the post and its table of contents"
+    )]
+    number_headings: bool,
+
+    #[arg(
+        long,
+        help = "transliterate accented and non-Latin letters in the slugs of titles, tags, authors and headings to ASCII (cafe-resume for Café Résumé, privet for Привет), hashing characters that can't be"
+    )]
+    slug_transliterate: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "also write pre-compressed .gz and/or .br versions of the generated files"
+    )]
+    precompress: Option<Precompression>,
+
+    #[arg(
+        long,
+        help = "date posts by their git history instead of filesystem times, and show when they were last updated"
+    )]
+    use_git_dates: bool,
+
+    #[arg(long, help = "show a progress bar while rendering the posts")]
+    progress: bool,
+
+    #[arg(short, long, help = "only print warnings and errors")]
+    quiet: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = MarkdownFlavor::Default,
+        help = "the preset of markdown extensions to use: commonmark (none), github (tables, task lists, strikethrough), default (github plus footnotes and smart punctuation), full (default plus heading attributes)"
+    )]
+    flavor: MarkdownFlavor,
+
+    #[arg(long, help = "enable tables on top of the markdown flavor")]
+    enable_tables: bool,
+
+    #[arg(long, help = "enable footnotes on top of the markdown flavor")]
+    enable_footnotes: bool,
+
+    #[arg(long, help = "enable strikethrough on top of the markdown flavor")]
+    enable_strikethrough: bool,
+
+    #[arg(long, help = "enable task lists on top of the markdown flavor")]
+    enable_tasklists: bool,
+
+    #[arg(long, help = "enable smart punctuation on top of the markdown flavor")]
+    enable_smart_punctuation: bool,
+
+    #[arg(long, help = "enable heading attributes on top of the markdown flavor")]
+    enable_heading_attributes: bool,
+
+    #[arg(
+        long,
+        help = "render the posts as plain CommonMark, whatever the flavor, and warn about markdown relying on an extension"
+    )]
+    strict_commonmark: bool,
+
+    #[arg(long, help = "make links to other sites open in a new tab")]
+    external_link_new_tab: bool,
+
+    #[arg(
+        long,
+        help = "rewrite the links in the posts to the site's own pages (under the base url) to be relative to each post"
+    )]
+    relative_links: bool,
+
+    #[arg(
+        long,
+        help = "attribute blockquotes ending in a line starting with an em dash (— Name) to Name, with a <cite>"
+    )]
+    blockquote_cite: bool,
+
+    #[arg(
+        long,
+        help = "the class of the <article> element wrapping each post's content"
+    )]
+    content_class: Option<String>,
+
+    #[arg(
+        long,
+        help = "put the title, date, tags and slug of each post in data- attributes on its <article>"
+    )]
+    data_attributes: bool,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u32).range(..=MAX_WRITE_RETRIES as i64),
+        help = "how many times (up to 10) to retry writing an output file before giving up, waiting up to 5s between tries"
+    )]
+    write_retries: u32,
+
+    #[arg(
+        long,
+        value_parser = parse_file_mode,
+        help = "the Unix permissions (in octal, like 644) to give every generated file"
+    )]
+    file_mode: Option<u32>,
+
+    #[arg(
+        long,
+        help = "don't hold a .build.lock file in the rendered outputs dir while building, which keeps two builds from writing into it at once"
+    )]
+    no_lock: bool,
+
+    #[arg(
+        long,
+        help = "path to a theme dir with a style.css, a post.html template, and optionally an index.html template and static/ files"
+    )]
+    theme: Option<String>,
+
+    #[arg(long, help = "path to a tera template to use for the posts")]
+    post_template: Option<String>,
+
+    #[arg(long, help = "path to a tera template to use for the index page")]
+    index_template: Option<String>,
+
+    #[arg(
+        long = "template-rule",
+        value_name = "PATTERN=FILE",
+        help = "use the tera template FILE for the posts whose file names match the glob PATTERN (like link-*.md=link.html); given more than once, the first matching rule wins"
+    )]
+    template_rules: Vec<TemplateRule>,
+
+    #[arg(
+        long,
+        help = "a nonce to set on the inline <style> tags, matching the site's Content-Security-Policy"
+    )]
+    csp_nonce: Option<String>,
+
+    #[arg(
+        long,
+        help = "also publish posts whose front matter date is still in the future"
+    )]
+    publish_future: bool,
+
+    #[arg(
+        long,
+        help = "also publish posts marked as drafts in their front matter"
+    )]
+    drafts: bool,
+
+    #[arg(
+        long,
+        help = "list the posts that would be published (file, title, date, tags and draft status) without building the site"
+    )]
+    list: bool,
+
+    #[arg(long, help = "show a cloud of the posts' tags on the index page")]
+    tag_cloud: bool,
+
+    #[arg(
+        long,
+        default_value = "%Y-%m-%d",
+        value_parser = parse_date_format,
+        help = "the strftime style format of the dates shown on the index and posts"
+    )]
+    date_format: String,
+
+    #[arg(
+        long,
+        help = "strip scripts, event handlers and other unsafe html from the posts; off by default, for trusted markdown"
+    )]
+    sanitize: bool,
+
+    #[arg(
+        long,
+        help = "leave the raw html blocks and inline html in the markdown out of the posts, except those with allow_html: true in their front matter"
+    )]
+    disable_raw_html: bool,
+
+    #[arg(
+        long,
+        requires = "toc",
+        help = "scroll smoothly to the headings linked from the table of contents"
+    )]
+    smooth_scroll: bool,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        requires = "smooth_scroll",
+        help = "how far above a heading (in pixels) to stop scrolling, e.g. to clear a fixed header"
+    )]
+    scroll_offset: u32,
+
+    #[arg(
+        long,
+        env = "SOURCE_DATE_EPOCH",
+        help = "a unix timestamp to use in place of the build time and filesystem times, for reproducible builds"
+    )]
+    source_date_epoch: Option<i64>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "only build the posts dated on or after DATE (YYYY-MM-DD), or within a time back like 7d, 12h or 2w"
+    )]
+    since: Option<Since>,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_MAX_FILE_SIZE,
+        help = "skip markdown files bigger than this many bytes"
+    )]
+    max_file_size: u64,
+
+    #[arg(
+        long,
+        help = "decode the markdown source files that aren't valid UTF-8 from this encoding (latin1, windows-1252, shift_jis, ...), rather than skipping them"
+    )]
+    source_encoding: Option<SourceEncoding>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "write the posts into date based subfolders, e.g. 2024/03/post.html for year/month"
+    )]
+    archive_layout: Option<ArchiveLayout>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        value_parser = parse_posts_subdir,
+        help = "write the posts into this subfolder, leaving the index and feeds at the root"
+    )]
+    posts_subdir: Option<String>,
+
+    #[arg(
+        long,
+        help = "give a post published at the same url as an older one a numbered suffix, rather than failing"
+    )]
+    disambiguate: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PostOrder::Date,
+        help = "how to order the posts: by date, or by the order in their front matter (with the posts that have none after the rest, by date)"
+    )]
+    sort: PostOrder,
+
+    #[arg(
+        long,
+        help = "the title of the site, shown in the header linking back to the index"
+    )]
+    site_title: Option<String>,
+
+    #[arg(
+        long,
+        help = "leave out the header linking back to the index from the posts"
+    )]
+    no_header: bool,
+
+    #[arg(
+        long,
+        help = "write an etags.json with the sha256 of each generated page, for serving as ETag headers"
+    )]
+    etags: bool,
+
+    #[arg(long, help = "make every output file end with exactly one newline")]
+    final_newline: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "convert the line endings of every output file"
+    )]
+    line_ending: Option<LineEnding>,
+
+    #[arg(
+        long,
+        help = "start every output file (other than the json ones) with a UTF-8 byte order mark"
+    )]
+    bom: bool,
+
+    #[arg(
+        long,
+        help = "also generate an archive.html listing the posts by year and month"
+    )]
+    archive: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "md_sources",
+        help = "read the posts from a single file, separated by ---8<--- lines, instead of a dir"
+    )]
+    bundle: Option<String>,
+
+    #[arg(
+        long,
+        help = "path to a file whose contents are included verbatim in the <head> of every post"
+    )]
+    head_include: Option<String>,
+
+    #[arg(
+        long,
+        help = "path to a file whose contents are included verbatim at the end of the <body> of every post"
+    )]
+    body_end_include: Option<String>,
+
+    #[arg(
+        long,
+        help = "also add the head and body end includes to the index pages"
+    )]
+    includes_on_index: bool,
+
+    #[arg(long, help = "add a button for copying the code to each code block")]
+    code_copy_button: bool,
+
+    #[arg(
+        long,
+        help = "path to a favicon (.ico, .png or .svg) to copy into the rendered outputs dir and link from every page"
+    )]
+    favicon: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "./",
+        help = "the url the site is served from; should be absolute (https://...) for the feeds"
+    )]
+    base_url: String,
+
+    #[arg(long, value_enum, help = "also generate feeds of the posts")]
+    feed_format: Option<FeedFormat>,
+
+    #[arg(
+        long,
+        value_enum,
+        requires = "feed_format",
+        default_value_t = FeedContent::Excerpt,
+        help = "what the feeds include of each post besides its title and link"
+    )]
+    feed_content: FeedContent,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "also write a recent.html fragment listing the N most recent posts, for embedding in other sites"
+    )]
+    recent_count: Option<usize>,
+
+    #[arg(
+        long,
+        help = "path to a YAML list of other blogs (title, feed_url and site_url), written out as blogroll.opml and blogroll.html"
+    )]
+    blogroll: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "WORDS",
+        default_value_t = DEFAULT_LONG_READ_THRESHOLD,
+        help = "flag the posts with more words than this as long reads"
+    )]
+    long_read_threshold: usize,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "shorten the titles in the index to N characters, with the full title on hover"
+    )]
+    index_title_max_chars: Option<usize>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["stdin", "list"],
+        help = "keep running, rebuilding the site whenever a markdown source (or the css) changes"
+    )]
+    watch: bool,
+
+    #[arg(
+        long,
+        requires = "watch",
+        help = "when only posts have changed while watching, write just their pages again (along with the index, feeds and listings), rather than every page"
+    )]
+    incremental: bool,
+
+    #[arg(
+        long,
+        help = "a file to keep the rendered markdown of each post in between runs, so unchanged posts aren't parsed again"
+    )]
+    cache_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "the maximum width of the page content in px, before the css source so it can override it"
+    )]
+    content_width: Option<u32>,
+
+    #[arg(
+        long,
+        value_parser = parse_font_scale,
+        help = "scale the base font size by this factor (1.0 being the browser's default), before the css source so it can override it"
+    )]
+    font_scale: Option<f32>,
+
+    #[arg(
+        long,
+        help = "generate an Open Graph image of each post's title (unless its front matter has an image), linked with og:image"
+    )]
+    og_images: bool,
+
+    #[arg(
+        long,
+        requires = "og_images",
+        help = "path to a background image for the Open Graph images, scaled and cropped to 1200x630"
+    )]
+    og_background: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "og_images",
+        help = "path to a TrueType or OpenType font for the text of the Open Graph images, in place of the first of DejaVu Sans, Liberation Sans or Arial installed"
+    )]
+    og_font: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "also write every post onto a single all.html page, with a table of contents at the top"
+    )]
+    single_page: bool,
+
+    #[arg(
+        long,
+        help = "also write each post as json (title, dates, tags, excerpt, html) next to its page, with a posts.json listing them"
+    )]
+    json_output: bool,
+
+    #[arg(
+        long,
+        help = "path to the critical part of the css, to inline in each page while the full css source is written to site.css and loaded asynchronously"
+    )]
+    critical_css: Option<String>,
+
+    #[arg(
+        long,
+        help = "add styles for printing the posts, leaving out the navigation"
+    )]
+    print_styles: bool,
+
+    #[arg(
+        long,
+        requires = "print_styles",
+        help = "path to the css rules to print the posts with, in place of the default print styles"
+    )]
+    print_css: Option<String>,
+
+    #[arg(
+        long,
+        help = "give each post a <link rel=\"canonical\"> to its own url under the base url (a front matter canonical url always wins)"
+    )]
+    canonical_urls: bool,
+
+    #[arg(
+        long,
+        help = "number images captioned by the line after them as figures, referred to with {{fig:label}}"
+    )]
+    figures: bool,
+
+    #[arg(
+        long,
+        help = "path to a file of reference-style link definitions shared by every post; a post's own definitions win"
+    )]
+    link_refs: Option<String>,
+
+    #[arg(
+        long,
+        help = "dir of the partials that {{ include \"...\" }} directives in the posts are resolved against"
+    )]
+    partials_dir: Option<String>,
+
+    #[arg(
+        long,
+        help = "read metadata from <!-- key: value --> comments at the top of the posts, as well as their front matter (which wins where both set a key)"
+    )]
+    comment_metadata: bool,
+
+    #[arg(
+        long,
+        help = "report the posts added, removed, retitled or changed since the previous build (going by its manifest.json)"
+    )]
+    report_changes: bool,
+
+    #[arg(
+        long,
+        help = "also print each post to a PDF next to its html, with a headless Chrome/Chromium or wkhtmltopdf (which has to be installed)"
+    )]
+    pdf: bool,
+
+    #[arg(
+        long,
+        requires = "pdf",
+        help = "the html to PDF renderer to use, instead of the first of chromium, google-chrome or wkhtmltopdf on the PATH"
+    )]
+    pdf_renderer: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with = "quiet",
+        help = "log what the generator is doing, and how many posts were taken from the cache"
+    )]
+    verbose: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "set up a new blog in DIR, with a sample post, a starter stylesheet and a sample md-blog-gen.conf, leaving any existing files alone"
+    )]
+    init: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "read options from FILE, one per line as name = value (or just name for a switch), with those on the command line taking precedence; md-blog-gen.conf is read from the current dir when there is one"
+    )]
+    config: Option<PathBuf>,
+}
+
+/// Splits a `name=path` css source into its name and path, naming a plain path after
+/// the css file.
+fn css_variant(css_source: &str) -> (String, String) {
+    match css_source.split_once('=') {
+        Some((name, path)) => (name.to_string(), path.to_string()),
+        None => {
+            let name = Path::new(css_source)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (name, css_source.to_string())
+        }
+    }
+}
+
+/// The css variants of the `css_sources`, each of which is rendered into a subfolder named
+/// after it, so no two can have the same name.
+fn css_variants(css_sources: &[String]) -> Result<Vec<(String, String)>> {
+    let variants: Vec<(String, String)> = css_sources.iter().map(|c| css_variant(c)).collect();
+    for (i, (name, _)) in variants.iter().enumerate() {
+        if variants[..i].iter().any(|(other, _)| other == name) {
+            return Err(anyhow!("more than one css source is named {:?}", name));
+        }
+    }
+
+    Ok(variants)
+}
+
+/// The command line, with the options from the config file (the `--config` one, or else
+/// `md-blog-gen.conf` in the current dir if there is one) put before the options given
+/// on it, for each option that isn't.
+fn command_line() -> Result<Vec<OsString>> {
+    let given: Vec<OsString> = std::env::args_os().collect();
+    let config_file = given
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| given.get(i + 1))
+        .map(PathBuf::from)
+        .or_else(|| {
+            given
+                .iter()
+                .find_map(|arg| arg.to_str()?.strip_prefix("--config=").map(PathBuf::from))
+        });
+    let initializing = given
+        .iter()
+        .filter_map(|arg| arg.to_str())
+        .any(|arg| arg == "--init" || arg.starts_with("--init="));
+    let config_file = match config_file {
+        Some(config_file) => config_file,
+        None if !initializing && Path::new(CONFIG_FILE).is_file() => PathBuf::from(CONFIG_FILE),
+        None => return Ok(given),
+    };
+
+    let config = std::fs::read_to_string(&config_file)
+        .with_context(|| format!("could not read the config file {}", config_file.display()))?;
+    let options = parse_config(&config).map_err(|e| {
+        anyhow!(
+            "the config file {} is invalid: {}",
+            config_file.display(),
+            e
+        )
+    })?;
+
+    let command = Args::command();
+    let mut command_line: Vec<OsString> = given.iter().take(1).cloned().collect();
+    for (name, value) in options {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "the config file {} has an unknown option: {}",
+                    config_file.display(),
+                    name
+                )
+            })?;
+        let long = format!("--{}", name);
+        let short = arg.get_short().map(|short| format!("-{}", short));
+        let is_given = given
+            .iter()
+            .skip(1)
+            .filter_map(|arg| arg.to_str())
+            .any(|arg| {
+                arg == long
+                    || arg.starts_with(&format!("{}=", long))
+                    || short.as_ref().is_some_and(|short| {
+                        arg.starts_with(short.as_str()) && !arg.starts_with("--")
+                    })
+            });
+        if !is_given {
+            command_line.push(match value {
+                Some(value) => format!("{}={}", long, value).into(),
+                None => long.into(),
+            });
+        }
+    }
+    command_line.extend(given.into_iter().skip(1));
+
+    Ok(command_line)
+}
+
+fn parse_font_scale(font_scale: &str) -> Result<f32, String> {
+    match font_scale.parse::<f32>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err(format!("{:?} is not a positive number", font_scale)),
+    }
+}
+
+fn parse_file_mode(file_mode: &str) -> Result<u32, String> {
+    match u32::from_str_radix(file_mode.trim_start_matches("0o"), 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("{:?} is not an octal file mode", file_mode)),
+    }
+}
+
+fn parse_posts_subdir(posts_subdir: &str) -> Result<String, String> {
+    let dir = posts_subdir.trim_matches('/');
+    let within_outputs = !dir.is_empty()
+        && !posts_subdir.starts_with('/')
+        && dir.split('/').all(|part| !matches!(part, "" | "." | ".."));
+    if !within_outputs {
+        return Err(format!(
+            "{:?} is not a subfolder of the rendered outputs dir",
+            posts_subdir
+        ));
+    }
+
+    Ok(dir.to_string())
+}
+
+fn parse_date_format(date_format: &str) -> Result<String, String> {
+    if StrftimeItems::new(date_format).any(|item| item == Item::Error) {
+        return Err(format!("{:?} is not a valid strftime format", date_format));
+    }
+
+    Ok(date_format.to_string())
+}
+
+impl Args {
+    fn extra_markdown_options(&self) -> Options {
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_TABLES, self.enable_tables);
+        options.set(Options::ENABLE_FOOTNOTES, self.enable_footnotes);
+        options.set(Options::ENABLE_STRIKETHROUGH, self.enable_strikethrough);
+        options.set(Options::ENABLE_TASKLISTS, self.enable_tasklists);
+        options.set(
+            Options::ENABLE_SMART_PUNCTUATION,
+            self.enable_smart_punctuation,
+        );
+        options.set(
+            Options::ENABLE_HEADING_ATTRIBUTES,
+            self.enable_heading_attributes,
+        );
+
+        options
+    }
 }
 
 fn main() -> Result<()> {
@@ -26,19 +836,261 @@ fn main() -> Result<()> {
     //let markdown_sources = "/home/pimeson/Development/RustDev/md-blog-gen/md-blog-gen/md_sources".to_string();
     //let rendered_outputs = "/home/pimeson/Development/RustDev/md-blog-gen/md-blog-gen/rendered_html".to_string();
 
-    let args = Args::parse();
+    let mut args = Args::parse_from(command_line()?);
+
+    let default_log_filter = if args.verbose {
+        "warn,md_blog_gen=debug"
+    } else {
+        "warn"
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_log_filter))
+        .init();
+
+    if let Some(dir) = &args.init {
+        let report = scaffold_blog(dir).map_err(|e| anyhow!("could not set up the blog: {}", e))?;
+        println!("{}", report);
+        return Ok(());
+    }
+
+    // urls are built by appending paths to the base url
+    if !args.base_url.ends_with('/') {
+        args.base_url.push('/');
+    }
+    let extra_markdown_options = args.extra_markdown_options();
+
+    let source_date_epoch = match args.source_date_epoch {
+        Some(secs) => Some(
+            DateTime::from_timestamp(secs, 0)
+                .ok_or_else(|| anyhow!("the source date epoch {} is out of range", secs))?,
+        ),
+        None => None,
+    };
+
+    // the individual css and template options win over the ones from the theme
+    let theme = args.theme.as_deref().map(Theme::load).transpose()?;
+    let blogroll = args.blogroll.as_deref().map(Blogroll::load).transpose()?;
+    let mut css_variants = css_variants(&args.css_source)?;
+    if let (true, Some(theme)) = (css_variants.is_empty(), &theme) {
+        css_variants.push(css_variant(&format!("{}", theme.css_source_file.display())));
+    }
+    let css_source = css_variants
+        .first()
+        .map(|(_, path)| path.clone())
+        .unwrap_or_default();
 
-    let br = BlogGenerator::new(
-        "./".to_string(),
-        args.css_source,
-        args.md_sources,
-        args.rendered_outputs,
-    )
+    let read_include = |path: Option<String>| -> Result<Option<String>> {
+        path.map(|path| {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("could not read the file {}", path))
+        })
+        .transpose()
+    };
+    let head_include = read_include(args.head_include)?;
+    let body_end_include = read_include(args.body_end_include)?;
+    let link_refs = read_include(args.link_refs)?;
+    let critical_css = read_include(args.critical_css)?;
+    let print_css = read_include(args.print_css)?;
+
+    // local images in a bundle are relative to the bundle file
+    let md_sources = args.md_sources.unwrap_or_else(|| {
+        args.bundle
+            .as_deref()
+            .and_then(|bundle| Path::new(bundle).parent())
+            .map(|dir| format!("{}", dir.display()))
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or_else(|| ".".to_string())
+    });
+
+    let pdf_renderer = match (args.pdf, args.pdf_renderer) {
+        (true, Some(renderer)) => Some(renderer),
+        (true, None) => Some(find_pdf_renderer().ok_or_else(|| {
+            anyhow!("--pdf needs chromium, google-chrome or wkhtmltopdf on the PATH (or a --pdf-renderer)")
+        })?),
+        (false, _) => None,
+    };
+
+    let br = if args.stdin {
+        BlogGenerator::new_standalone(args.base_url.clone(), css_source)
+    } else {
+        BlogGenerator::new(
+            args.base_url.clone(),
+            css_source,
+            md_sources,
+            args.rendered_outputs.unwrap_or_default(),
+        )
+    }
+    .map(|r| {
+        r.with_bare(args.bare, args.keep_index)
+            .with_strict(args.strict)
+            .with_resolve_css_imports(args.resolve_css_imports)
+            .with_check_alt(args.check_alt)
+            .with_check_headings(args.check_headings)
+            .with_not_found_page(args.not_found_page, args.not_found_source)
+            .with_heading_offset(args.heading_offset)
+            .with_index_excerpts(args.index_excerpts)
+            .with_copy_images(args.copy_images, args.optimize_images)
+            .with_inline_small_images(args.inline_small_images)
+            .with_toc(args.toc, args.toc_depth)
+            .with_number_headings(args.number_headings)
+            .with_slug_transliterate(args.slug_transliterate)
+            .with_precompress(args.precompress)
+            .with_git_dates(args.use_git_dates)
+            .with_progress(args.progress)
+            .with_quiet(args.quiet)
+            .with_markdown_flavor(args.flavor, extra_markdown_options)
+            .with_strict_commonmark(args.strict_commonmark)
+            .with_external_links_new_tab(args.external_link_new_tab)
+            .with_relative_links(args.relative_links)
+            .with_blockquote_cite(args.blockquote_cite)
+            .with_content_class(args.content_class)
+            .with_data_attributes(args.data_attributes)
+            .with_write_retries(args.write_retries)
+            .with_file_mode(args.file_mode)
+            .with_build_lock(!args.no_lock)
+            .with_templates(args.post_template, args.index_template)
+            .with_template_rules(args.template_rules)
+            .with_theme(theme)
+            .with_csp_nonce(args.csp_nonce)
+            .with_publish_future(args.publish_future)
+            .with_drafts(args.drafts)
+            .with_tag_cloud(args.tag_cloud)
+            .with_date_format(args.date_format)
+            .with_sanitize(args.sanitize)
+            .with_disable_raw_html(args.disable_raw_html)
+            .with_smooth_scroll(args.smooth_scroll, args.scroll_offset)
+            .with_source_date_epoch(source_date_epoch)
+            .with_since(args.since)
+            .with_max_file_size(args.max_file_size)
+            .with_source_encoding(args.source_encoding)
+            .with_archive_layout(args.archive_layout)
+            .with_posts_subdir(args.posts_subdir)
+            .with_disambiguate(args.disambiguate)
+            .with_post_order(args.sort)
+            .with_site_header(!args.no_header, args.site_title)
+            .with_etags(args.etags)
+            .with_output_format(args.final_newline, args.line_ending, args.bom)
+            .with_archive(args.archive)
+            .with_bundle(args.bundle)
+            .with_includes(head_include, body_end_include, args.includes_on_index)
+            .with_code_copy_button(args.code_copy_button)
+            .with_favicon(args.favicon)
+            .with_feeds(args.feed_format, args.feed_content)
+            .with_recent_posts(args.recent_count)
+            .with_blogroll(blogroll)
+            .with_long_read_threshold(args.long_read_threshold)
+            .with_index_title_max_chars(args.index_title_max_chars)
+            .with_cache(args.watch, args.cache_file.clone())
+            .with_pdf(pdf_renderer)
+            .with_report_changes(args.report_changes)
+            .with_link_refs(link_refs)
+            .with_partials_dir(args.partials_dir)
+            .with_comment_metadata(args.comment_metadata)
+            .with_figures(args.figures)
+            .with_canonical_urls(args.canonical_urls)
+            .with_critical_css(critical_css)
+            .with_print_styles(args.print_styles, print_css)
+            .with_og_images(args.og_images, args.og_background)
+            .with_og_font(args.og_font)
+            .with_single_page(args.single_page)
+            .with_json_output(args.json_output)
+            .with_layout(args.content_width, args.font_scale)
+    })
     .map_err(|e| eprintln!("{}", e));
 
     if let Ok(r) = br {
-        r.render()?;
+        if args.stdin {
+            let mut md_content = String::new();
+            std::io::stdin().read_to_string(&mut md_content)?;
+            print!("{}", r.render_str(&md_content)?);
+        } else if args.list {
+            println!("{}", r.list_posts()?);
+        } else if css_variants.len() > 1 {
+            for (name, summary) in r.render_css_variants(&css_variants)? {
+                if !args.quiet {
+                    println!("{}:\n{}", name, summary);
+                }
+            }
+        } else {
+            let build = |changed: Option<&[PathBuf]>| -> Result<()> {
+                let summary = match changed {
+                    Some(changed) => r.render_changed(changed)?,
+                    None => r.render()?,
+                };
+                if !args.quiet {
+                    println!("{}", summary);
+                } else if !summary.problems.is_empty() {
+                    eprintln!("{}", summary.problems);
+                }
+                if let (true, Some(hits), Some(misses)) =
+                    (args.verbose, summary.cache_hits, summary.cache_misses)
+                {
+                    println!("cache hits: {}, misses: {}", hits, misses);
+                }
+                Ok(())
+            };
+            build(None)?;
+
+            if args.watch {
+                println!("watching for changes, press ctrl-c to stop");
+                let mut last_modified = modified_times(&r.watched_files());
+                loop {
+                    thread::sleep(Duration::from_millis(500));
+                    let modified = modified_times(&r.watched_files());
+                    if modified == last_modified {
+                        continue;
+                    }
+                    // the files that have changed, been added or gone
+                    let changed: Vec<PathBuf> = modified
+                        .keys()
+                        .chain(last_modified.keys())
+                        .filter(|path| modified.get(*path) != last_modified.get(*path))
+                        .cloned()
+                        .collect::<BTreeSet<_>>()
+                        .into_iter()
+                        .collect();
+                    last_modified = modified;
+
+                    if !args.quiet {
+                        let changed: Vec<String> = changed
+                            .iter()
+                            .map(|path| format!("{}", path.display()))
+                            .collect();
+                        println!("rebuilding, changed: {}", changed.join(", "));
+                    }
+
+                    // a broken post shouldn't stop the watching, it'll get fixed and saved again
+                    let changed = args.incremental.then_some(changed.as_slice());
+                    if let Err(e) = build(changed) {
+                        eprintln!("{:#}", e);
+                    }
+                }
+            }
+        }
     };
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn css_variants_are_named_after_their_files() {
+        let variants =
+            css_variants(&["light=a.css".to_string(), "css/dark.css".to_string()]).unwrap();
+        assert_eq!(
+            variants,
+            vec![
+                ("light".to_string(), "a.css".to_string()),
+                ("dark".to_string(), "css/dark.css".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn css_variants_with_the_same_name_are_rejected() {
+        assert!(css_variants(&["a/dark.css".to_string(), "b/dark.css".to_string()]).is_err());
+        assert!(css_variants(&["dark=a.css".to_string(), "dark.css".to_string()]).is_err());
+    }
+}